@@ -13,11 +13,15 @@ use shuttle_common::models::{
         DeleteCertificateRequest,
     },
     deployment::{
-        DeploymentListResponse, DeploymentRequest, DeploymentResponse, UploadArchiveResponse,
+        ArchiveBlobsRequest, ArchiveBlobsResponse, ArchiveManifest, DeploymentListResponse,
+        DeploymentRequest, DeploymentResponse, UploadArchiveResponse,
     },
     log::LogsResponse,
     project::{ProjectCreateRequest, ProjectListResponse, ProjectResponse, ProjectUpdateRequest},
-    resource::{ProvisionResourceRequest, ResourceListResponse, ResourceResponse, ResourceType},
+    resource::{
+        ProvisionResourceRequest, ResourceListResponse, ResourceResponse, ResourceType,
+        SecretsUpdateRequest,
+    },
     team::TeamListResponse,
     user::UserResponse,
 };
@@ -130,6 +134,53 @@ impl ShuttleApiClient {
             .await
     }
 
+    /// Find out which of the given file blobs have to be uploaded
+    pub async fn check_archive_blobs(
+        &self,
+        project: &str,
+        hashes: Vec<String>,
+    ) -> Result<ParsedJson<ArchiveBlobsResponse>> {
+        self.post_json(
+            format!("/projects/{project}/archives/blobs"),
+            Some(ArchiveBlobsRequest { hashes }),
+        )
+        .await
+    }
+
+    pub async fn upload_archive_blob(
+        &self,
+        project: &str,
+        hash: &str,
+        data: Vec<u8>,
+    ) -> Result<()> {
+        let path = format!("/projects/{project}/archives/blobs/{hash}");
+
+        let url = format!("{}{}", self.api_url, path);
+        let mut builder = self.client.put(url);
+        builder = self.set_auth_bearer(builder);
+
+        builder
+            .body(data)
+            .send()
+            .await
+            .context("failed to upload archive blob")?
+            .to_empty()
+            .await
+    }
+
+    /// Assemble an archive from previously uploaded file blobs
+    pub async fn upload_archive_manifest(
+        &self,
+        project: &str,
+        manifest: ArchiveManifest,
+    ) -> Result<ParsedJson<UploadArchiveResponse>> {
+        self.post_json(
+            format!("/projects/{project}/archives/manifest"),
+            Some(manifest),
+        )
+        .await
+    }
+
     pub async fn redeploy(
         &self,
         project: &str,
@@ -201,6 +252,14 @@ impl ShuttleApiClient {
         self.get_json(format!("/projects/{project}/resources/secrets"))
             .await
     }
+    pub async fn update_secrets(
+        &self,
+        project: &str,
+        req: SecretsUpdateRequest,
+    ) -> Result<ParsedJson<ResourceResponse>> {
+        self.put_json(format!("/projects/{project}/resources/secrets"), Some(req))
+            .await
+    }
 
     pub async fn list_certificates(
         &self,
@@ -282,6 +341,22 @@ impl ShuttleApiClient {
 
         self.get_json(path).await
     }
+    pub async fn get_deployment_logs_ws(
+        &self,
+        project: &str,
+        deployment_id: &str,
+    ) -> Result<WebSocketStream<MaybeTlsStream<TcpStream>>> {
+        self.ws_get(format!(
+            "/projects/{project}/deployments/{deployment_id}/logs/ws"
+        ))
+        .await
+    }
+    pub async fn get_project_logs_ws(
+        &self,
+        project: &str,
+    ) -> Result<WebSocketStream<MaybeTlsStream<TcpStream>>> {
+        self.ws_get(format!("/projects/{project}/logs/ws")).await
+    }
 
     pub async fn get_deployments(
         &self,
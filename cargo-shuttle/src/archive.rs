@@ -0,0 +1,494 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+use anyhow::{Context, Result};
+use globset::{Glob, GlobSetBuilder};
+use ignore::overrides::OverrideBuilder;
+use ignore::WalkBuilder;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use shuttle_common::models::deployment::{ArchiveManifest, ArchiveManifestEntry};
+use tracing::trace;
+
+/// Paths that are never included unless explicitly requested in Shuttle.toml
+const DEFAULT_EXCLUDES: &[&str] = &[".git", "target"];
+
+/// Why a path in the project directory was left out of the deployment archive
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, strum::Display)]
+#[serde(rename_all = "snake_case")]
+#[strum(serialize_all = "snake_case")]
+pub enum ExcludeReason {
+    /// Matched a rule in .gitignore, .ignore or similar files
+    Gitignore,
+    /// Is in one of the default excluded directories (`.git/` and `target/`)
+    DefaultExclude,
+    /// Symlinks are not followed
+    Symlink,
+    /// Directories can't be added to an archive, so empty ones are lost
+    Directory,
+}
+
+/// All files found when gathering the build files
+#[derive(Default)]
+pub struct BuildFiles {
+    /// Absolute path -> path in archive
+    pub included: BTreeMap<PathBuf, PathBuf>,
+    /// Path relative to the project directory -> reason for not including it
+    pub excluded: BTreeMap<PathBuf, ExcludeReason>,
+}
+
+/// Find list of all files in `project_directory` to include in a build, ready for placing in a zip archive.
+/// Also keeps track of what was left out and why.
+pub fn gather_build_files(
+    project_directory: &Path,
+    include_patterns: Option<&Vec<String>>,
+) -> Result<BuildFiles> {
+    let entries = walk_build_files(project_directory, include_patterns, false)?;
+
+    let mut files = BuildFiles::default();
+    for path in entries.iter() {
+        // zip file puts all files in root
+        let name = path
+            .strip_prefix(project_directory)
+            .context("strip prefix of path")?
+            .to_owned();
+
+        // It's not possible to add a directory to an archive
+        if path.is_dir() {
+            trace!("Skipping {:?}: is a directory", path);
+            if path
+                .read_dir()
+                .is_ok_and(|mut entries| entries.next().is_none())
+            {
+                files.excluded.insert(name, ExcludeReason::Directory);
+            }
+            continue;
+        }
+        // symlinks == chaos
+        if path.is_symlink() {
+            trace!("Skipping {:?}: is a symlink", path);
+            files.excluded.insert(name, ExcludeReason::Symlink);
+            continue;
+        }
+
+        files.included.insert(path.clone(), name);
+    }
+
+    // Everything that neither of the walks found was ignored.
+    // Ignored directories are reported once instead of listing all of their contents,
+    // unless something inside of them was included.
+    let found: BTreeSet<&Path> = entries.iter().map(PathBuf::as_path).collect();
+    // Directories with something found inside of them, so that each path is looked up once
+    let found_parents: BTreeSet<&Path> = entries
+        .iter()
+        .flat_map(|path| {
+            path.ancestors()
+                .skip(1)
+                .take_while(|parent| parent.starts_with(project_directory))
+        })
+        .collect();
+    let mut walker = walkdir::WalkDir::new(project_directory).into_iter();
+    while let Some(entry) = walker.next() {
+        let entry = entry.context("list dir")?;
+        let path = entry.path();
+        if found.contains(path) {
+            continue;
+        }
+        let is_dir = entry.file_type().is_dir();
+        if is_dir && found_parents.contains(path) {
+            continue;
+        }
+        let name = path
+            .strip_prefix(project_directory)
+            .context("strip prefix of path")?
+            .to_owned();
+        let reason = if is_default_excluded(&name) {
+            ExcludeReason::DefaultExclude
+        } else {
+            ExcludeReason::Gitignore
+        };
+        files.excluded.insert(name, reason);
+        if is_dir {
+            walker.skip_current_dir();
+        }
+    }
+
+    Ok(files)
+}
+
+/// Find the files to include in a build, without keeping track of what was left out.
+/// Cheaper than [`gather_build_files`], for when the files are listed repeatedly.
+/// Files in the default excluded directories are not listed, even if an include pattern matches them,
+/// since builds keep writing to those.
+pub fn list_build_files(
+    project_directory: &Path,
+    include_patterns: Option<&Vec<String>>,
+) -> Result<BTreeMap<PathBuf, PathBuf>> {
+    let mut files = BTreeMap::new();
+    for path in walk_build_files(project_directory, include_patterns, true)? {
+        if path.is_dir() || path.is_symlink() {
+            continue;
+        }
+        let name = path
+            .strip_prefix(project_directory)
+            .context("strip prefix of path")?
+            .to_owned();
+        files.insert(path, name);
+    }
+
+    Ok(files)
+}
+
+/// All paths found by the ignore walk and the include walk, which can contain duplicates.
+fn walk_build_files(
+    project_directory: &Path,
+    include_patterns: Option<&Vec<String>>,
+    prune_default_excludes: bool,
+) -> Result<Vec<PathBuf>> {
+    //
+    // Mixing include and exclude overrides messes up the .ignore and .gitignore etc,
+    // therefore these "ignore" walk and the "include" walk are separate.
+    //
+    let mut entries = Vec::new();
+
+    // Default excludes
+    let mut ignore_overrides = OverrideBuilder::new(project_directory);
+    for dir in DEFAULT_EXCLUDES {
+        ignore_overrides
+            .add(&format!("!{dir}/"))
+            .with_context(|| format!("adding override `!{dir}/`"))?;
+    }
+    let ignore_overrides = ignore_overrides
+        .build()
+        .context("building archive override rules")?;
+    for r in WalkBuilder::new(project_directory)
+        .hidden(false)
+        .overrides(ignore_overrides)
+        .build()
+    {
+        entries.push(r.context("list dir entry")?.into_path())
+    }
+
+    // User provided includes
+    let Some(rules) = include_patterns.filter(|r| !r.is_empty()) else {
+        return Ok(entries);
+    };
+    let mut globs = GlobSetBuilder::new();
+    for r in rules {
+        globs.add(Glob::new(r.as_str()).context(format!("parsing glob pattern {:?}", r))?);
+    }
+
+    // Find the files
+    let globs = globs.build().context("glob glob")?;
+    let walker = walkdir::WalkDir::new(project_directory)
+        .into_iter()
+        .filter_entry(|e| {
+            !prune_default_excludes
+                || !e
+                    .path()
+                    .strip_prefix(project_directory)
+                    .is_ok_and(is_default_excluded)
+        });
+    for entry in walker {
+        let path = entry.context("list dir")?.into_path();
+        if globs.is_match(
+            path.strip_prefix(project_directory)
+                .context("strip prefix of path")?,
+        ) {
+            entries.push(path);
+        }
+    }
+
+    Ok(entries)
+}
+
+/// Whether a path relative to the project directory is in one of the default excluded directories
+fn is_default_excluded(name: &Path) -> bool {
+    matches!(
+        name.components().next(),
+        Some(Component::Normal(first)) if DEFAULT_EXCLUDES.iter().any(|d| first == *d)
+    )
+}
+
+/// The path of a file inside of the archive
+pub fn archive_path(name: &Path) -> String {
+    // windows things
+    name.to_str().expect("valid filename").replace('\\', "/")
+}
+
+/// Hash the contents of all files and describe the archive in terms of them.
+/// Also returns where to find the contents of each hash.
+pub fn build_manifest(
+    archive_files: &BTreeMap<PathBuf, PathBuf>,
+) -> Result<(ArchiveManifest, BTreeMap<String, PathBuf>)> {
+    let mut files = Vec::with_capacity(archive_files.len());
+    let mut blobs = BTreeMap::new();
+    for (path, name) in archive_files {
+        let bytes = fs::read(path).with_context(|| format!("reading {}", path.display()))?;
+        let hash = hex::encode(Sha256::digest(&bytes));
+        files.push(ArchiveManifestEntry {
+            path: archive_path(name),
+            hash: hash.clone(),
+        });
+        blobs.insert(hash, path.clone());
+    }
+
+    Ok((ArchiveManifest { files }, blobs))
+}
+
+/// Local record of which file blobs have already been uploaded to a project
+pub struct BlobCache {
+    path: PathBuf,
+    hashes: BTreeSet<String>,
+}
+
+impl BlobCache {
+    pub fn load(project_id: &str) -> Result<Self> {
+        let path = dirs::cache_dir()
+            .context("Could not find a cache directory")?
+            .join("shuttle")
+            .join("archive-blobs")
+            .join(format!("{project_id}.json"));
+        let hashes = match fs::read_to_string(&path) {
+            Ok(s) => serde_json::from_str(&s).unwrap_or_else(|e| {
+                // the cache is just an optimization, so start over if it is broken
+                trace!(error = %e, "discarding unreadable blob cache");
+                BTreeSet::new()
+            }),
+            Err(_) => BTreeSet::new(),
+        };
+
+        Ok(Self { path, hashes })
+    }
+
+    pub fn contains(&self, hash: &str) -> bool {
+        self.hashes.contains(hash)
+    }
+
+    pub fn extend(&mut self, hashes: impl IntoIterator<Item = String>) {
+        self.hashes.extend(hashes);
+    }
+
+    pub fn clear(&mut self) {
+        self.hashes.clear();
+    }
+
+    pub fn save(&self) -> Result<()> {
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir).context("creating blob cache directory")?;
+        }
+        fs::write(&self.path, serde_json::to_string(&self.hashes)?)
+            .context("writing blob cache")?;
+
+        Ok(())
+    }
+}
+
+/// The `n` largest files, biggest first
+pub fn largest_files(
+    archive_files: &BTreeMap<PathBuf, PathBuf>,
+    n: usize,
+) -> Result<Vec<(PathBuf, u64)>> {
+    let mut sizes = Vec::with_capacity(archive_files.len());
+    for (path, name) in archive_files {
+        let size = path
+            .metadata()
+            .with_context(|| format!("reading metadata of {}", path.display()))?
+            .len();
+        sizes.push((name.clone(), size));
+    }
+    sizes.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    sizes.truncate(n);
+
+    Ok(sizes)
+}
+
+/// Report of what a deployment archive would contain
+#[derive(Serialize)]
+pub struct ArchiveReport {
+    pub files: Vec<ArchiveReportFile>,
+    pub excluded: Vec<ArchiveReportExclusion>,
+    /// Sum of the uncompressed file sizes
+    pub total_size: u64,
+    /// Size of the compressed archive
+    pub archive_size: u64,
+}
+
+#[derive(Serialize)]
+pub struct ArchiveReportFile {
+    pub path: PathBuf,
+    pub size: u64,
+}
+
+#[derive(Serialize)]
+pub struct ArchiveReportExclusion {
+    pub path: PathBuf,
+    pub reason: ExcludeReason,
+}
+
+impl ArchiveReport {
+    pub fn new(files: &BuildFiles, archive_size: u64) -> Result<Self> {
+        let mut report_files = Vec::with_capacity(files.included.len());
+        for (path, name) in files.included.iter() {
+            let size = path
+                .metadata()
+                .with_context(|| format!("reading metadata of {}", path.display()))?
+                .len();
+            report_files.push(ArchiveReportFile {
+                path: name.clone(),
+                size,
+            });
+        }
+        report_files.sort_by(|a, b| a.path.cmp(&b.path));
+
+        Ok(Self {
+            total_size: report_files.iter().map(|f| f.size).sum(),
+            files: report_files,
+            excluded: files
+                .excluded
+                .iter()
+                .map(|(path, reason)| ArchiveReportExclusion {
+                    path: path.clone(),
+                    reason: *reason,
+                })
+                .collect(),
+            archive_size,
+        })
+    }
+
+    pub fn to_string_colored(&self) -> String {
+        use crossterm::style::Stylize;
+        use std::fmt::Write;
+
+        let mut s = String::new();
+        writeln!(s, "{}", "Files to be archived:".bold()).unwrap();
+        for f in self.files.iter() {
+            writeln!(s, "  {:>10}  {}", format_size(f.size), f.path.display()).unwrap();
+        }
+        if !self.excluded.is_empty() {
+            writeln!(s).unwrap();
+            writeln!(s, "{}", "Excluded:".bold()).unwrap();
+            for e in self.excluded.iter() {
+                writeln!(
+                    s,
+                    "  {} {}",
+                    e.path.display(),
+                    format!("({})", e.reason.to_string().replace('_', " ")).dim()
+                )
+                .unwrap();
+            }
+        }
+        writeln!(s).unwrap();
+        write!(
+            s,
+            "{} files, {} total, {} compressed archive",
+            self.files.len(),
+            format_size(self.total_size),
+            format_size(self.archive_size),
+        )
+        .unwrap();
+
+        s
+    }
+}
+
+/// Human readable file size
+pub fn format_size(bytes: u64) -> String {
+    const UNITS: &[&str] = &["B", "KiB", "MiB", "GiB"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{bytes} B")
+    } else {
+        format!("{size:.1} {}", UNITS[unit])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn manifest_dedupes_blobs() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir_all(dir.path().join("src")).unwrap();
+        fs::write(dir.path().join("a"), "same").unwrap();
+        fs::write(dir.path().join("src").join("b"), "same").unwrap();
+        fs::write(dir.path().join("c"), "different").unwrap();
+        let files = ["a", "src/b", "c"]
+            .into_iter()
+            .map(|n| (dir.path().join(n), PathBuf::from(n)))
+            .collect();
+
+        let (manifest, blobs) = build_manifest(&files).unwrap();
+
+        assert_eq!(manifest.files.len(), 3);
+        assert_eq!(blobs.len(), 2);
+        let a = manifest.files.iter().find(|f| f.path == "a").unwrap();
+        let b = manifest.files.iter().find(|f| f.path == "src/b").unwrap();
+        assert_eq!(a.hash, b.hash);
+        // sha256 of "same"
+        assert_eq!(
+            a.hash,
+            "0967115f2813a3541eaef77de9d9d5773f1c0c04314b0bbfe4ff3b3b1c55b5d5"
+        );
+    }
+
+    #[test]
+    fn list_build_files_skips_default_excludes() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir_all(dir.path().join("src")).unwrap();
+        fs::create_dir_all(dir.path().join("target/assets")).unwrap();
+        fs::write(dir.path().join("src/main.rs"), "").unwrap();
+        fs::write(dir.path().join("target/app"), "").unwrap();
+        fs::write(dir.path().join("target/assets/logo.png"), "").unwrap();
+        let include = vec!["target/assets/*".to_string()];
+
+        let names = |files: BTreeMap<PathBuf, PathBuf>| files.into_values().collect::<Vec<_>>();
+        assert_eq!(
+            names(list_build_files(dir.path(), Some(&include)).unwrap()),
+            vec![PathBuf::from("src/main.rs")]
+        );
+        assert_eq!(
+            names(
+                gather_build_files(dir.path(), Some(&include))
+                    .unwrap()
+                    .included
+            ),
+            vec![
+                PathBuf::from("src/main.rs"),
+                PathBuf::from("target/assets/logo.png")
+            ]
+        );
+    }
+
+    #[test]
+    fn largest_files_sorted() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("small"), "1").unwrap();
+        fs::write(dir.path().join("big"), "12345").unwrap();
+        fs::write(dir.path().join("medium"), "123").unwrap();
+        let files = ["small", "big", "medium"]
+            .into_iter()
+            .map(|n| (dir.path().join(n), PathBuf::from(n)))
+            .collect();
+
+        assert_eq!(
+            largest_files(&files, 2).unwrap(),
+            vec![(PathBuf::from("big"), 5), (PathBuf::from("medium"), 3)]
+        );
+    }
+
+    #[test]
+    fn sizes() {
+        assert_eq!(format_size(0), "0 B");
+        assert_eq!(format_size(1023), "1023 B");
+        assert_eq!(format_size(1024), "1.0 KiB");
+        assert_eq!(format_size(200 * 1024 * 1024), "200.0 MiB");
+    }
+}
@@ -7,11 +7,13 @@ use std::{
 
 use anyhow::{bail, Context};
 use cargo_metadata::MetadataCommand;
+use chrono::{DateTime, TimeDelta, Utc};
 use clap::{
     builder::{OsStringValueParser, PossibleValue, TypedValueParser},
     Args, Parser, Subcommand, ValueEnum,
 };
 use clap_complete::Shell;
+use regex::Regex;
 use shuttle_common::{constants::EXAMPLES_REPO, models::resource::ResourceType};
 
 #[derive(Parser)]
@@ -49,7 +51,7 @@ pub struct ShuttleArgs {
         env = "SHUTTLE_OUTPUT_MODE",
         default_value = "normal"
     )]
-    pub output_mode: OutputMode,
+    pub output_mode: OutputFormat,
     #[command(flatten)]
     pub project_args: ProjectArgs,
 
@@ -57,12 +59,25 @@ pub struct ShuttleArgs {
     pub cmd: Command,
 }
 
-#[derive(ValueEnum, Clone, Debug, Default, PartialEq)]
+/// The formats that can be picked with `--output`
+#[derive(ValueEnum, Clone, Copy, Debug, Default, PartialEq)]
+pub enum OutputFormat {
+    #[default]
+    Normal,
+    Json,
+    /// One JSON object per log line. Other output is printed as JSON
+    #[value(alias = "jsonl")]
+    Ndjson,
+    /// One logfmt record per log line. Other output is printed normally
+    Logfmt,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
 pub enum OutputMode {
     #[default]
     Normal,
     Json,
-    // TODO?: add table / non-table / raw table / raw logs variants?
+    // TODO?: add table / non-table / raw table variants?
 }
 
 /// Global project-related options
@@ -129,6 +144,11 @@ pub enum Command {
     Run(RunArgs),
     /// Deploy a project
     Deploy(DeployArgs),
+    /// Build a Docker image of the project with the local Docker daemon, the same way the platform does
+    Build(ImageBuildArgs),
+    /// Manage the resource containers that `shuttle run` provisions
+    #[command(subcommand)]
+    Local(LocalCommand),
     /// Manage deployments
     #[command(subcommand, visible_alias = "depl")]
     Deployment(DeploymentCommand),
@@ -140,6 +160,9 @@ pub enum Command {
     /// Manage resources
     #[command(subcommand, visible_alias = "res")]
     Resource(ResourceCommand),
+    /// Manage project secrets
+    #[command(subcommand, visible_alias = "secret")]
+    Secrets(SecretsCommand),
     /// Manage SSL certificates for custom domains
     #[command(subcommand, visible_alias = "cert")]
     Certificate(CertificateCommand),
@@ -224,6 +247,18 @@ pub enum DeploymentCommand {
         #[command(flatten)]
         tracking_args: DeploymentTrackingArgs,
     },
+    /// Redeploy the most recent deployment before the current one that reached the running state
+    Rollback {
+        #[command(flatten)]
+        tracking_args: DeploymentTrackingArgs,
+    },
+    /// Compare the build metadata, build arguments, infra and secret keys of two deployments
+    Diff {
+        /// ID of the deployment to compare from
+        a: String,
+        /// ID of the deployment to compare to. Defaults to the current deployment
+        b: Option<String>,
+    },
     /// Stop running deployment(s)
     Stop {
         #[command(flatten)]
@@ -253,16 +288,135 @@ pub enum ResourceCommand {
         #[command(flatten)]
         confirmation: ConfirmationArgs,
     },
-    /// Dump a resource
-    #[command(hide = true)] // not yet supported on shuttle.dev
+    /// Dump a database resource as SQL
     Dump {
         /// Type of the resource to dump.
         /// Use the string in the 'Type' column as displayed in the `resource list` command.
         /// For example, 'database::shared::postgres'.
         resource_type: ResourceType,
+        /// Dump the database provisioned by `shuttle run` (currently required)
+        #[arg(long)]
+        local: bool,
+        /// Name of the database, if a custom one is set in the resource annotation
+        #[arg(long)]
+        database: Option<String>,
+        /// File to write the dump to, instead of stdout
+        #[arg(long = "file", short = 'o')]
+        output: Option<PathBuf>,
+    },
+    /// Load an SQL dump into a database resource
+    Restore {
+        /// Type of the resource to restore into.
+        /// For example, 'database::shared::postgres'.
+        resource_type: ResourceType,
+        /// Restore into the database provisioned by `shuttle run` (currently required)
+        #[arg(long)]
+        local: bool,
+        /// Name of the database, if a custom one is set in the resource annotation
+        #[arg(long)]
+        database: Option<String>,
+        /// SQL file to load, such as one created by `shuttle resource dump`
+        #[arg(long, short)]
+        input: PathBuf,
+        #[command(flatten)]
+        confirmation: ConfirmationArgs,
+    },
+    /// Remove a locally provisioned database and its volume, so that the next `shuttle run` starts from scratch
+    Reset {
+        /// Type of the resource to reset.
+        /// For example, 'database::shared::postgres'.
+        resource_type: ResourceType,
+        /// Reset the resource provisioned by `shuttle run`
+        #[arg(long)]
+        local: bool,
+        #[command(flatten)]
+        confirmation: ConfirmationArgs,
+    },
+}
+
+impl ResourceCommand {
+    /// Whether the command acts on locally provisioned resources instead of the platform
+    pub fn is_local(&self) -> bool {
+        matches!(
+            self,
+            Self::Dump { local: true, .. }
+                | Self::Restore { local: true, .. }
+                | Self::Reset { local: true, .. }
+        )
+    }
+}
+
+#[derive(Subcommand)]
+pub enum LocalCommand {
+    /// List the resource containers of this project
+    Ps {
+        #[command(flatten)]
+        table: TableArgs,
+    },
+    /// Stop resource containers, keeping their data
+    Stop {
+        /// Only stop the container of this resource, as listed by `shuttle local ps`, such as 'database::shared::postgres'
+        resource: Option<String>,
+    },
+    /// Remove resource containers
+    Down {
+        /// Only remove the container of this resource, as listed by `shuttle local ps`, such as 'database::shared::postgres'
+        resource: Option<String>,
+        /// Also remove the volumes of the containers, deleting all data
+        #[arg(long)]
+        volumes: bool,
+        #[command(flatten)]
+        confirmation: ConfirmationArgs,
+    },
+    /// Print the logs of a resource container
+    Logs {
+        /// The resource, as listed by `shuttle local ps`, such as 'database::shared::postgres'
+        resource: String,
+        /// Keep streaming new log lines
+        #[arg(short, long)]
+        follow: bool,
+        /// Only show this many of the latest lines
+        #[arg(long)]
+        tail: Option<u32>,
     },
 }
 
+#[derive(Subcommand)]
+pub enum SecretsCommand {
+    /// List the secrets for a project
+    #[command(visible_alias = "ls")]
+    List {
+        /// Show secret values instead of hiding them
+        #[arg(long, default_value_t = false)]
+        show_values: bool,
+
+        #[command(flatten)]
+        table: TableArgs,
+    },
+    /// Add or overwrite secrets
+    Set {
+        /// Secrets to set, as KEY=VALUE.
+        /// If only KEY is given, the value is prompted for so that it does not end up in shell history.
+        #[arg(required = true)]
+        secrets: Vec<String>,
+    },
+    /// Remove secrets
+    #[command(visible_alias = "rm")]
+    Unset {
+        /// Keys of the secrets to remove
+        #[arg(required = true)]
+        keys: Vec<String>,
+    },
+    /// Add or overwrite secrets from a secrets file
+    Import {
+        /// Path to the secrets file (TOML, .env, JSON or YAML)
+        #[arg(value_parser = OsStringValueParser::new().try_map(parse_path))]
+        file: PathBuf,
+    },
+    /// Print the secrets for a project in Secrets.toml format
+    Export,
+}
+
 #[derive(Subcommand)]
 pub enum CertificateCommand {
     /// Add an SSL certificate for a custom domain
@@ -349,6 +503,24 @@ pub struct DeployArgs {
     /// WIP: Deploy this Docker image instead of building one
     #[arg(long, short = 'i', hide = true)]
     pub image: Option<String>,
+    /// WIP: Username for pulling the image from a private registry.
+    /// Without it, credentials are looked up in the Docker config file.
+    #[arg(long, requires = "image", hide = true)]
+    pub image_username: Option<String>,
+    /// WIP: Read the password for the private registry from stdin instead of prompting for it
+    #[arg(long, requires = "image_username", hide = true)]
+    pub image_password_stdin: bool,
+    /// Deploy the project from this git repository URL instead of the local files.
+    /// The repository is not checked out locally, so the package and binary are found by the builder,
+    /// and the deployment records the commit and branch but not the commit message.
+    #[arg(long, requires = "rev", conflicts_with_all = ["image", "output_archive", "dry_run"])]
+    pub git: Option<String>,
+    /// The branch, tag or full commit hash to deploy from the git repository
+    #[arg(long, requires = "git")]
+    pub rev: Option<String>,
+    /// Path to the project directory within the git repository
+    #[arg(long, requires = "git")]
+    pub subfolder: Option<String>,
 
     /// Allow deployment with uncommitted files
     #[arg(long, visible_alias = "ad")]
@@ -356,13 +528,60 @@ pub struct DeployArgs {
     /// Output the deployment archive to a file instead of sending a deployment request
     #[arg(long)]
     pub output_archive: Option<PathBuf>,
+    /// List the files that would be archived and the ones that are excluded, without deploying
+    #[arg(long, conflicts_with_all = ["image", "output_archive"])]
+    pub dry_run: bool,
+    /// If the deployment fails, redeploy the last deployment that reached the running state
+    #[arg(long, conflicts_with = "no_follow")]
+    pub rollback_on_failure: bool,
 
+    #[command(flatten)]
+    pub cargo_args: CargoBuildArgs,
     #[command(flatten)]
     pub tracking_args: DeploymentTrackingArgs,
 
     #[command(flatten)]
     pub secret_args: SecretsArgs,
 }
+#[derive(Args, Default)]
+pub struct ImageBuildArgs {
+    /// Tag for the built image [default: <package name>:latest]
+    #[arg(long, short = 't')]
+    pub tag: Option<String>,
+    /// Don't use cargo chef to cache the dependency build
+    #[arg(long)]
+    pub no_cargo_chef: bool,
+    /// Link with the mold linker
+    #[arg(long)]
+    pub mold: bool,
+    /// Print the generated Dockerfile instead of building the image
+    #[arg(long)]
+    pub print_dockerfile: bool,
+
+    #[command(flatten)]
+    pub cargo_args: CargoBuildArgs,
+}
+
+/// Overrides for the `[build]` section in Shuttle.toml
+#[derive(Args, Clone, Debug, Default)]
+pub struct CargoBuildArgs {
+    /// Package to build [default: the first package with a `#[shuttle_runtime::main]` binary].
+    /// Can be given several times to `run` to run multiple services.
+    #[arg(long)]
+    pub package: Vec<String>,
+    /// Binary to build [default: the binary with `#[shuttle_runtime::main]`]
+    #[arg(long)]
+    pub bin: Option<String>,
+    /// Space or comma separated list of features to activate.
+    /// Packages with a `shuttle` feature are always built with it, and these are activated in addition.
+    #[arg(long, short = 'F')]
+    pub features: Vec<String>,
+    /// Do not activate the `default` feature [default: true if the package has a `shuttle` feature].
+    /// Use `--no-default-features=false` to activate it even if Shuttle.toml disables it.
+    #[arg(long, num_args = 0..=1, default_missing_value = "true", require_equals = true)]
+    pub no_default_features: Option<bool>,
+}
+
 #[derive(Args, Default)]
 pub struct DeploymentTrackingArgs {
     /// Don't follow the deployment status, exit after the operation begins
@@ -390,16 +609,33 @@ pub struct RunArgs {
     /// Uses bacon crate to run the project in watch mode
     #[arg(long)]
     pub bacon: bool,
+    /// Rebuild and restart the service when project files change
+    #[arg(long, short = 'w', conflicts_with = "bacon")]
+    pub watch: bool,
+    /// Run all packages in the workspace that have a `#[shuttle_runtime::main]` binary
+    #[arg(long, conflicts_with_all = ["package", "bin", "bacon"])]
+    pub all: bool,
 
+    #[command(flatten)]
+    pub cargo_args: CargoBuildArgs,
     #[command(flatten)]
     pub secret_args: SecretsArgs,
+    #[command(flatten)]
+    pub log_filter: LogFilterArgs,
 }
 
 #[derive(Args, Debug, Default)]
 pub struct SecretsArgs {
-    /// Use this secrets file instead
+    /// Use this secrets file instead.
+    /// TOML, .env, JSON and YAML files are supported, detected by file name.
     #[arg(long, value_parser = OsStringValueParser::new().try_map(parse_path))]
     pub secrets: Option<PathBuf>,
+    /// Use the secrets profile in Secrets.<PROFILE>.toml
+    #[arg(long, value_parser = parse_profile_name)]
+    pub profile: Option<String>,
+    /// Layer the profile's secrets on top of the base secrets file instead of using them alone
+    #[arg(long, requires = "profile")]
+    pub layer: bool,
 }
 
 #[derive(Args, Clone, Debug, Default)]
@@ -520,24 +756,49 @@ pub struct LogsArgs {
     #[arg(short, long)]
     /// View logs from the most recent deployment (which is not always the running one)
     pub latest: bool,
-    #[arg(short, long, hide = true)]
+    #[arg(short, long, conflicts_with = "head")]
     /// Follow log output
     pub follow: bool,
     /// Don't display timestamps and log origin tags
     #[arg(long)]
     pub raw: bool,
-    /// View the first N log lines
-    #[arg(long, group = "pagination", hide = true)]
+    /// View the first N log lines.
+    /// All logs are still fetched, since the API has no range parameters, and are sliced locally
+    #[arg(long, group = "pagination")]
     pub head: Option<u32>,
-    /// View the last N log lines
-    #[arg(long, group = "pagination", hide = true)]
+    /// View the last N log lines.
+    /// All logs are still fetched, since the API has no range parameters, and are sliced locally
+    #[arg(long, group = "pagination")]
     pub tail: Option<u32>,
     /// View all log lines
-    #[arg(long, group = "pagination", hide = true)]
+    #[arg(long, group = "pagination")]
     pub all: bool,
     /// Get logs from all deployments instead of one deployment
-    #[arg(long, hide = true)]
+    #[arg(long)]
     pub all_deployments: bool,
+
+    #[command(flatten)]
+    pub filter: LogFilterArgs,
+}
+
+#[derive(Args, Clone, Debug, Default)]
+#[command(next_help_heading = "Log filter options")]
+pub struct LogFilterArgs {
+    /// Only show log lines from after this time (RFC 3339, or relative such as 15m, 2h, 1d)
+    #[arg(long, value_parser = parse_log_time)]
+    pub since: Option<DateTime<Utc>>,
+    /// Only show log lines from before this time (RFC 3339, or relative such as 15m, 2h, 1d)
+    #[arg(long, value_parser = parse_log_time)]
+    pub until: Option<DateTime<Utc>>,
+    /// Only show log lines matching this regex
+    #[arg(long)]
+    pub grep: Option<Regex>,
+    /// Only show log lines from this source. Can be given multiple times
+    #[arg(long = "source")]
+    pub sources: Vec<String>,
+    /// Show the log lines that do NOT match --grep and --source instead
+    #[arg(long)]
+    pub invert: bool,
 }
 
 /// Helper function to parse and return the absolute path
@@ -550,6 +811,41 @@ fn parse_path(path: OsString) -> Result<PathBuf, io::Error> {
     })
 }
 
+/// Parse an RFC 3339 timestamp, or a duration such as `15m` that is relative to now
+fn parse_log_time(s: &str) -> Result<DateTime<Utc>, String> {
+    if let Ok(t) = DateTime::parse_from_rfc3339(s) {
+        return Ok(t.with_timezone(&Utc));
+    }
+
+    let invalid = || format!("'{s}' is not an RFC 3339 timestamp or a duration like 15m, 2h, 1d");
+    let split = s.find(|c: char| !c.is_ascii_digit()).ok_or_else(invalid)?;
+    let (amount, unit) = s.split_at(split);
+    let amount = amount.parse::<i64>().map_err(|_| invalid())?;
+    let duration = match unit {
+        "s" => TimeDelta::try_seconds(amount),
+        "m" => TimeDelta::try_minutes(amount),
+        "h" => TimeDelta::try_hours(amount),
+        "d" => TimeDelta::try_days(amount),
+        "w" => TimeDelta::try_weeks(amount),
+        _ => None,
+    }
+    .ok_or_else(invalid)?;
+
+    Ok(Utc::now() - duration)
+}
+
+fn parse_profile_name(name: &str) -> Result<String, String> {
+    if !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    {
+        Ok(name.to_owned())
+    } else {
+        Err("profile names can only contain letters, numbers, '-' and '_'".to_owned())
+    }
+}
+
 /// Helper function to parse, create if not exists, and return the absolute path
 pub(crate) fn create_and_parse_path(path: OsString) -> Result<PathBuf, io::Error> {
     // Create the directory if does not exist
@@ -567,13 +863,48 @@ pub(crate) fn create_and_parse_path(path: OsString) -> Result<PathBuf, io::Error
 mod tests {
     use super::*;
     use crate::tests::path_from_workspace_root;
-    use clap::CommandFactory;
+    use clap::{CommandFactory, FromArgMatches};
 
     #[test]
     fn test_shuttle_args() {
         ShuttleArgs::command().debug_assert();
     }
 
+    #[test]
+    fn test_no_default_features_flag() {
+        let parse = |args: &[&str]| {
+            let mut all = vec!["test"];
+            all.extend(args);
+            CargoBuildArgs::augment_args(clap::Command::new("test"))
+                .try_get_matches_from(all)
+                .and_then(|m| CargoBuildArgs::from_arg_matches(&m))
+                .unwrap()
+                .no_default_features
+        };
+
+        assert_eq!(parse(&[]), None);
+        assert_eq!(parse(&["--no-default-features"]), Some(true));
+        assert_eq!(parse(&["--no-default-features=false"]), Some(false));
+    }
+
+    #[test]
+    fn test_parse_log_time() {
+        assert_eq!(
+            parse_log_time("2024-05-01T12:00:00+02:00").unwrap(),
+            DateTime::parse_from_rfc3339("2024-05-01T10:00:00Z").unwrap()
+        );
+
+        let before = Utc::now();
+        let t = parse_log_time("15m").unwrap();
+        assert!(t >= before - TimeDelta::minutes(15));
+        assert!(t <= Utc::now() - TimeDelta::minutes(15));
+
+        assert!(parse_log_time("15").is_err());
+        assert!(parse_log_time("m").is_err());
+        assert!(parse_log_time("15y").is_err());
+        assert!(parse_log_time("yesterday").is_err());
+    }
+
     #[test]
     fn test_init_args_framework() {
         // pre-defined template (only hello world)
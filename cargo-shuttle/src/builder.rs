@@ -12,14 +12,52 @@ use tracing::{error, trace};
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub struct BuiltService {
     pub workspace_path: PathBuf,
+    pub package_name: String,
     pub target_name: String,
     pub executable_path: PathBuf,
 }
 
+/// Which package, binary and features to build.
+/// Unset options fall back to the first Shuttle package in the workspace and its `shuttle` feature.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct CargoBuildOptions {
+    pub package: Option<String>,
+    pub bin: Option<String>,
+    pub features: Vec<String>,
+    /// Defaults to true for packages with a `shuttle` feature
+    pub no_default_features: Option<bool>,
+}
+
+impl CargoBuildOptions {
+    /// The `--no-default-features` flag and comma-separated `--features` to build `package` with.
+    ///
+    /// Packages with a `shuttle` feature are always built with it, and without default features unless
+    /// `no_default_features` is set to false. Other features are activated in addition to it.
+    pub fn cargo_features(&self, package: &Package) -> (bool, Option<String>) {
+        let shuttle_feature = package.features.contains_key("shuttle");
+
+        let mut features = Vec::new();
+        if shuttle_feature {
+            features.push("shuttle");
+        }
+        for feature in self.features.iter().flat_map(|f| f.split([',', ' '])) {
+            if !feature.is_empty() && !features.contains(&feature) {
+                features.push(feature);
+            }
+        }
+
+        (
+            self.no_default_features.unwrap_or(shuttle_feature),
+            (!features.is_empty()).then(|| features.join(",")),
+        )
+    }
+}
+
 /// Builds Shuttle service in given project directory
 pub async fn build_workspace(
     project_path: &Path,
     release_mode: bool,
+    options: &CargoBuildOptions,
     tx: tokio::sync::mpsc::Sender<String>,
 ) -> Result<BuiltService> {
     let project_path = project_path.to_owned();
@@ -56,12 +94,13 @@ pub async fn build_workspace(
     notification.abort();
 
     let metadata = async_cargo_metadata(manifest_path.as_path()).await?;
-    let (package, target, _) = find_first_shuttle_package(&metadata)?;
+    let (package, target, _) = find_shuttle_package(&metadata, options)?;
 
     let service = cargo_build(
         package,
         target,
         release_mode,
+        options,
         project_path.clone(),
         metadata.target_directory.clone(),
         tx.clone(),
@@ -99,8 +138,12 @@ pub async fn async_cargo_metadata(manifest_path: &Path) -> Result<Metadata> {
     Ok(metadata)
 }
 
-/// Find crates with a runtime dependency and main macro
-fn find_shuttle_packages(metadata: &Metadata) -> Result<Vec<(Package, Target, Option<String>)>> {
+/// Find crates with a runtime dependency and main macro.
+/// If `bin` is given, only that binary target is considered.
+pub fn find_shuttle_packages(
+    metadata: &Metadata,
+    bin: Option<&str>,
+) -> Result<Vec<(Package, Target, Option<String>)>> {
     let mut packages = Vec::new();
     trace!("Finding Shuttle-related packages");
     for member in metadata.workspace_packages() {
@@ -122,6 +165,7 @@ fn find_shuttle_packages(metadata: &Metadata) -> Result<Vec<(Package, Target, Op
         let mut target = None;
         for t in member.targets.iter() {
             if t.is_bin()
+                && bin.is_none_or(|bin| t.name == bin)
                 && find_runtime_main_fn(
                     &fs::read_to_string(t.src_path.as_std_path())
                         .context("reading to check for shuttle macro")?,
@@ -152,16 +196,43 @@ fn find_shuttle_packages(metadata: &Metadata) -> Result<Vec<(Package, Target, Op
 pub fn find_first_shuttle_package(
     metadata: &Metadata,
 ) -> Result<(Package, Target, Option<String>)> {
-    find_shuttle_packages(metadata)?.into_iter().next().context(
+    find_shuttle_packages(metadata, None)?.into_iter().next().context(
         "Expected at least one target that Shuttle can build. \
         Make sure your crate has a binary target that uses a fully qualified `#[shuttle_runtime::main]`.",
     )
 }
 
+/// Find the crate and binary selected by `options`, or the first Shuttle crate if none is selected
+pub fn find_shuttle_package(
+    metadata: &Metadata,
+    options: &CargoBuildOptions,
+) -> Result<(Package, Target, Option<String>)> {
+    if options.package.is_none() && options.bin.is_none() {
+        return find_first_shuttle_package(metadata);
+    }
+
+    find_shuttle_packages(metadata, options.bin.as_deref())?
+        .into_iter()
+        .find(|(p, _, _)| options.package.as_ref().is_none_or(|name| &p.name == name))
+        .with_context(|| {
+            format!(
+                "Expected {} to be a binary target that Shuttle can build. \
+                Make sure it has a shuttle-runtime dependency and uses a fully qualified `#[shuttle_runtime::main]`.",
+                match (&options.package, &options.bin) {
+                    (Some(package), Some(bin)) => format!("binary `{bin}` in package `{package}`"),
+                    (Some(package), None) => format!("a binary in package `{package}`"),
+                    (None, Some(bin)) => format!("binary `{bin}`"),
+                    (None, None) => unreachable!(),
+                }
+            )
+        })
+}
+
 async fn cargo_build(
     package: Package,
     target: Target,
     release_mode: bool,
+    options: &CargoBuildOptions,
     project_path: PathBuf,
     target_path: impl Into<PathBuf>,
     tx: tokio::sync::mpsc::Sender<String>,
@@ -181,8 +252,12 @@ async fn cargo_build(
         .arg("--color=always") // piping disables auto color, but we want it
         .current_dir(project_path.as_path());
 
-    if package.features.contains_key("shuttle") {
-        cmd.arg("--no-default-features").arg("--features=shuttle");
+    let (no_default_features, features) = options.cargo_features(&package);
+    if no_default_features {
+        cmd.arg("--no-default-features");
+    }
+    if let Some(features) = features {
+        cmd.arg(format!("--features={features}"));
     }
     cmd.arg("--package").arg(package.name.as_str());
     cmd.arg("--bin").arg(target.name.as_str());
@@ -224,7 +299,174 @@ async fn cargo_build(
 
     Ok(BuiltService {
         workspace_path: project_path.clone(),
+        package_name: package.name,
         target_name: target.name,
         executable_path: path,
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A workspace with two Shuttle packages and one without shuttle-runtime
+    fn workspace() -> (tempfile::TempDir, Metadata) {
+        let dir = tempfile::tempdir().unwrap();
+        let write = |path: &str, content: &str| {
+            let path = dir.path().join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, content).unwrap();
+        };
+        let main = "#[shuttle_runtime::main]\nasync fn main() -> shuttle_axum::ShuttleAxum {}";
+
+        write(
+            "Cargo.toml",
+            "[workspace]\nmembers = [\"api\", \"worker\", \"shared\"]\nresolver = \"2\"",
+        );
+        write(
+            "api/Cargo.toml",
+            r#"
+            [package]
+            name = "api"
+            version = "0.1.0"
+            edition = "2021"
+
+            [[bin]]
+            name = "migrate"
+            path = "src/bin/migrate.rs"
+
+            [[bin]]
+            name = "api"
+            path = "src/main.rs"
+
+            [dependencies]
+            shuttle-runtime = "0.56.0"
+
+            [features]
+            default = ["standalone"]
+            standalone = []
+            shuttle = []
+            "#,
+        );
+        write("api/src/main.rs", main);
+        write("api/src/bin/migrate.rs", "fn main() {}");
+        write(
+            "worker/Cargo.toml",
+            r#"
+            [package]
+            name = "worker"
+            version = "0.1.0"
+            edition = "2021"
+
+            [dependencies]
+            shuttle-runtime = "0.56.0"
+            "#,
+        );
+        write("worker/src/main.rs", main);
+        write(
+            "shared/Cargo.toml",
+            "[package]\nname = \"shared\"\nversion = \"0.1.0\"\nedition = \"2021\"",
+        );
+        write("shared/src/lib.rs", "");
+
+        let metadata = cargo_metadata::MetadataCommand::new()
+            .manifest_path(dir.path().join("Cargo.toml"))
+            .no_deps()
+            .exec()
+            .unwrap();
+
+        (dir, metadata)
+    }
+
+    fn package<'a>(metadata: &'a Metadata, name: &str) -> &'a Package {
+        metadata.packages.iter().find(|p| p.name == name).unwrap()
+    }
+
+    #[test]
+    fn finds_shuttle_packages() {
+        let (_dir, metadata) = workspace();
+
+        let found = find_shuttle_packages(&metadata, None)
+            .unwrap()
+            .into_iter()
+            .map(|(p, t, v)| (p.name, t.name, v))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            found,
+            vec![
+                (
+                    "api".to_owned(),
+                    "api".to_owned(),
+                    Some("0.56.0".to_owned())
+                ),
+                (
+                    "worker".to_owned(),
+                    "worker".to_owned(),
+                    Some("0.56.0".to_owned())
+                ),
+            ]
+        );
+        assert!(find_shuttle_packages(&metadata, Some("migrate"))
+            .unwrap()
+            .is_empty());
+
+        let options = |package: Option<&str>, bin: Option<&str>| CargoBuildOptions {
+            package: package.map(ToOwned::to_owned),
+            bin: bin.map(ToOwned::to_owned),
+            ..Default::default()
+        };
+        let (p, t, _) = find_shuttle_package(&metadata, &options(None, None)).unwrap();
+        assert_eq!((p.name.as_str(), t.name.as_str()), ("api", "api"));
+        let (p, _, _) = find_shuttle_package(&metadata, &options(Some("worker"), None)).unwrap();
+        assert_eq!(p.name, "worker");
+        let (p, _, _) = find_shuttle_package(&metadata, &options(None, Some("worker"))).unwrap();
+        assert_eq!(p.name, "worker");
+
+        let err = find_shuttle_package(&metadata, &options(Some("shared"), None)).unwrap_err();
+        assert!(err.to_string().contains("a binary in package `shared`"));
+        let err =
+            find_shuttle_package(&metadata, &options(Some("api"), Some("migrate"))).unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("binary `migrate` in package `api`"));
+    }
+
+    #[test]
+    fn cargo_features_keep_shuttle_feature() {
+        let (_dir, metadata) = workspace();
+        let api = package(&metadata, "api");
+        let worker = package(&metadata, "worker");
+        let options = |features: &[&str], no_default_features| CargoBuildOptions {
+            features: features.iter().map(|f| f.to_string()).collect(),
+            no_default_features,
+            ..Default::default()
+        };
+
+        // the implicit build of packages with a shuttle feature
+        assert_eq!(
+            options(&[], None).cargo_features(api),
+            (true, Some("shuttle".to_owned()))
+        );
+        assert_eq!(options(&[], None).cargo_features(worker), (false, None));
+
+        // explicit features are added to the shuttle feature
+        assert_eq!(
+            options(&["metrics,tls", "shuttle", "tls"], None).cargo_features(api),
+            (true, Some("shuttle,metrics,tls".to_owned()))
+        );
+        assert_eq!(
+            options(&["metrics tls"], None).cargo_features(worker),
+            (false, Some("metrics,tls".to_owned()))
+        );
+
+        // default features can be turned back on, for example to override Shuttle.toml
+        assert_eq!(
+            options(&[], Some(false)).cargo_features(api),
+            (false, Some("shuttle".to_owned()))
+        );
+        assert_eq!(
+            options(&[], Some(true)).cargo_features(worker),
+            (true, None)
+        );
+    }
+}
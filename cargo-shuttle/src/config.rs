@@ -1,3 +1,4 @@
+use std::collections::HashMap;
 use std::path::{Path, PathBuf};
 
 use anyhow::{anyhow, Context, Result};
@@ -43,6 +44,7 @@ pub struct ProjectConfig {
     pub assets: Option<Vec<String>>,
     pub deploy: Option<ProjectDeployConfig>,
     pub build: Option<ProjectBuildConfig>,
+    pub local: Option<ProjectLocalConfig>,
 }
 /// Deployment command config
 #[derive(Deserialize, Serialize, Default)]
@@ -52,6 +54,26 @@ pub struct ProjectDeployConfig {
     pub include: Option<Vec<String>>,
     /// Set to true to deny deployments with uncommited changes. (use `--allow-dirty` to override)
     pub deny_dirty: Option<bool>,
+    /// Set to true to only upload files that changed since the previous deployment
+    pub incremental_upload: Option<bool>,
+    /// Compression method for the deployment archive
+    pub compression: Option<ArchiveCompression>,
+    /// Compression level. The valid range depends on the compression method.
+    pub compression_level: Option<i64>,
+    /// Warn about single files larger than this many bytes
+    pub large_file_warning: Option<u64>,
+    /// Abort the deployment if the compressed archive is larger than this many bytes.
+    /// Incremental uploads are assembled remotely, so the sum of the uncompressed file sizes is checked instead.
+    pub max_archive_size: Option<u64>,
+}
+/// Compression methods supported in deployment archives
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ArchiveCompression {
+    Stored,
+    #[default]
+    Deflate,
+    Zstd,
 }
 /// Builder config
 #[derive(Deserialize, Serialize, Default)]
@@ -59,6 +81,74 @@ pub struct ProjectBuildConfig {
     /// Successor to `build_assets`.
     /// Patterns of files that should be copied from the build to the runtime container.
     pub assets: Option<Vec<String>>,
+    /// Package to build. Defaults to the first package with a `#[shuttle_runtime::main]` binary.
+    pub package: Option<String>,
+    /// Binary to build. Defaults to the binary with `#[shuttle_runtime::main]`.
+    pub bin: Option<String>,
+    /// Features to activate, in addition to `shuttle` if the package has that feature.
+    pub features: Option<Vec<String>>,
+    /// Set to true to not activate the `default` feature.
+    /// Defaults to true if the package has a `shuttle` feature.
+    pub no_default_features: Option<bool>,
+}
+
+/// Local run config
+#[derive(Deserialize, Serialize, Clone, Debug, Default)]
+pub struct ProjectLocalConfig {
+    /// Docker engine to use for `shuttle run` and `shuttle build` instead of the default one, such as `unix:///run/user/1000/podman/podman.sock` or `tcp://localhost:2375`
+    pub docker_host: Option<String>,
+    /// When to pull the images of local containers
+    pub pull_policy: Option<PullPolicy>,
+    /// Config for locally provisioned resources, keyed by resource type.
+    /// For example, `[local.resources."database::shared::postgres"]`.
+    /// Containers requested by resource crates are keyed by their container name, such as `[local.resources.qdrant]`.
+    pub resources: Option<HashMap<String, LocalResourceConfig>>,
+}
+
+impl ProjectLocalConfig {
+    pub fn resource(&self, key: &str) -> Option<&LocalResourceConfig> {
+        self.resources.as_ref().and_then(|r| r.get(key))
+    }
+}
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum PullPolicy {
+    /// Pull the image every time a container is created
+    #[default]
+    Always,
+    /// Only pull the image if it is not available locally
+    Missing,
+    /// Never pull, the image has to be available locally
+    Never,
+}
+/// Config for a locally provisioned resource
+#[derive(Deserialize, Serialize, Clone, Debug, Default)]
+pub struct LocalResourceConfig {
+    /// Image to use instead of the default one, for example from an internal mirror
+    pub image: Option<String>,
+    /// SQL file, or directory of SQL files, to run once on a local database that has not been seeded yet.
+    /// Relative to the project root. The `seed` option in the resource annotation takes precedence.
+    pub seed: Option<PathBuf>,
+    /// Keep the data in a named Docker volume, so that it survives the container being removed.
+    /// `true` uses a volume named after the project and resource type.
+    pub volume: Option<LocalVolume>,
+}
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+#[serde(untagged)]
+pub enum LocalVolume {
+    Enabled(bool),
+    Named(String),
+}
+
+impl LocalVolume {
+    /// Name of the volume to mount, if enabled
+    pub fn name(&self, default: &str) -> Option<String> {
+        match self {
+            Self::Enabled(true) => Some(default.to_owned()),
+            Self::Enabled(false) => None,
+            Self::Named(name) => Some(name.clone()),
+        }
+    }
 }
 
 /// .shuttle/config.toml schema (internal project-local config)
@@ -325,6 +415,58 @@ impl RequestContext {
             .and_then(|d| d.deny_dirty)
     }
 
+    /// # Panics
+    /// Panics if the project configuration has not been loaded.
+    pub fn incremental_upload(&self) -> Option<bool> {
+        self.project
+            .as_ref()
+            .unwrap()
+            .as_ref()
+            .unwrap()
+            .deploy
+            .as_ref()
+            .and_then(|d| d.incremental_upload)
+    }
+
+    /// # Panics
+    /// Panics if the project configuration has not been loaded.
+    pub fn deploy_config(&self) -> Option<&ProjectDeployConfig> {
+        self.project
+            .as_ref()
+            .unwrap()
+            .as_ref()
+            .unwrap()
+            .deploy
+            .as_ref()
+    }
+
+    /// # Panics
+    /// Panics if the project configuration has not been loaded.
+    pub fn build_config(&self) -> Option<&ProjectBuildConfig> {
+        self.project
+            .as_ref()
+            .unwrap()
+            .as_ref()
+            .unwrap()
+            .build
+            .as_ref()
+    }
+
+    /// Config for locally provisioned resources, keyed by resource type
+    ///
+    /// # Panics
+    /// Panics if the project configuration has not been loaded.
+    pub fn local_config(&self) -> ProjectLocalConfig {
+        self.project
+            .as_ref()
+            .unwrap()
+            .as_ref()
+            .unwrap()
+            .local
+            .clone()
+            .unwrap_or_default()
+    }
+
     /// Check if the current project id has been loaded.
     pub fn project_id_found(&self) -> bool {
         self.project_internal
@@ -359,7 +501,7 @@ mod tests {
 
     use crate::{args::ProjectArgs, config::RequestContext};
 
-    use super::{Config, LocalConfigManager, ProjectConfig};
+    use super::{Config, LocalConfigManager, ProjectConfig, PullPolicy};
 
     fn path_from_workspace_root(path: &str) -> PathBuf {
         PathBuf::from(std::env::var("CARGO_MANIFEST_DIR").unwrap())
@@ -397,6 +539,55 @@ mod tests {
         assert_eq!(unwrap_project_name(&local_config), "workspace");
     }
 
+    #[test]
+    fn parses_local_resources() {
+        let config: ProjectConfig = toml::from_str(
+            r#"
+            [local]
+            docker_host = "unix:///run/podman/podman.sock"
+            pull_policy = "missing"
+
+            [local.resources."database::shared::postgres"]
+            volume = true
+            image = "mirror.internal/postgres:16"
+
+            [local.resources."database::aws_rds::mysql"]
+            volume = "my-data"
+            "#,
+        )
+        .unwrap();
+        let local = config.local.unwrap();
+        assert_eq!(
+            local.docker_host.as_deref(),
+            Some("unix:///run/podman/podman.sock")
+        );
+        assert_eq!(local.pull_policy, Some(PullPolicy::Missing));
+        assert_eq!(
+            local
+                .resource("database::shared::postgres")
+                .and_then(|r| r.image.as_deref()),
+            Some("mirror.internal/postgres:16")
+        );
+        let resources = local.resources.unwrap();
+
+        assert_eq!(
+            resources["database::shared::postgres"]
+                .volume
+                .as_ref()
+                .unwrap()
+                .name("default"),
+            Some("default".to_owned())
+        );
+        assert_eq!(
+            resources["database::aws_rds::mysql"]
+                .volume
+                .as_ref()
+                .unwrap()
+                .name("default"),
+            Some("my-data".to_owned())
+        );
+    }
+
     #[test]
     fn setting_name_overrides_name_in_config() {
         let project_args = ProjectArgs {
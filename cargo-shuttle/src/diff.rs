@@ -0,0 +1,304 @@
+use std::collections::BTreeSet;
+
+use anyhow::Result;
+use crossterm::style::Stylize;
+use git2::{Oid, Repository};
+use serde::Serialize;
+use shuttle_common::models::deployment::{BuildArgs, BuildArgsRust, DeploymentResponse};
+
+/// A field that has a different value in the two deployments
+#[derive(Debug, PartialEq, Serialize)]
+pub struct FieldChange {
+    pub field: &'static str,
+    pub a: Option<String>,
+    pub b: Option<String>,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+pub struct Commit {
+    pub id: String,
+    pub summary: Option<String>,
+}
+
+/// What changed between deployment `a` and deployment `b`
+#[derive(Debug, Serialize)]
+pub struct DeploymentDiff {
+    pub a: String,
+    pub b: String,
+    pub changes: Vec<FieldChange>,
+    /// Secret keys that are only in `b`
+    pub secrets_added: Vec<String>,
+    /// Secret keys that are only in `a`
+    pub secrets_removed: Vec<String>,
+    /// Commits from `a` (exclusive) to `b` (inclusive), newest first. None if not known.
+    pub commits: Option<Vec<Commit>>,
+}
+
+impl DeploymentDiff {
+    pub fn new(a: &DeploymentResponse, b: &DeploymentResponse) -> Self {
+        let mut changes = Vec::new();
+        let mut field = |field, a: Option<String>, b: Option<String>| {
+            if a != b {
+                changes.push(FieldChange { field, a, b });
+            }
+        };
+
+        let (ma, mb) = (a.build_meta.as_ref(), b.build_meta.as_ref());
+        field(
+            "git_commit_id",
+            ma.and_then(|m| m.git_commit_id.clone()),
+            mb.and_then(|m| m.git_commit_id.clone()),
+        );
+        field(
+            "git_commit_msg",
+            ma.and_then(|m| m.git_commit_msg.clone()),
+            mb.and_then(|m| m.git_commit_msg.clone()),
+        );
+        field(
+            "git_branch",
+            ma.and_then(|m| m.git_branch.clone()),
+            mb.and_then(|m| m.git_branch.clone()),
+        );
+        field(
+            "git_dirty",
+            ma.and_then(|m| m.git_dirty).map(|d| d.to_string()),
+            mb.and_then(|m| m.git_dirty).map(|d| d.to_string()),
+        );
+        field(
+            "secrets_profile",
+            ma.and_then(|m| m.secrets_profile.clone()),
+            mb.and_then(|m| m.secrets_profile.clone()),
+        );
+
+        let (ra, rb) = (rust_build_args(a), rust_build_args(b));
+        field(
+            "shuttle_runtime_version",
+            ra.and_then(|r| r.shuttle_runtime_version.clone()),
+            rb.and_then(|r| r.shuttle_runtime_version.clone()),
+        );
+        field(
+            "package_name",
+            ra.and_then(|r| r.package_name.clone()),
+            rb.and_then(|r| r.package_name.clone()),
+        );
+        field(
+            "binary_name",
+            ra.and_then(|r| r.binary_name.clone()),
+            rb.and_then(|r| r.binary_name.clone()),
+        );
+        field(
+            "features",
+            ra.and_then(|r| r.features.clone()),
+            rb.and_then(|r| r.features.clone()),
+        );
+        field(
+            "no_default_features",
+            ra.map(|r| r.no_default_features.to_string()),
+            rb.map(|r| r.no_default_features.to_string()),
+        );
+        field(
+            "cargo_chef",
+            ra.map(|r| r.cargo_chef.to_string()),
+            rb.map(|r| r.cargo_chef.to_string()),
+        );
+        field(
+            "cargo_build",
+            ra.map(|r| r.cargo_build.to_string()),
+            rb.map(|r| r.cargo_build.to_string()),
+        );
+        field(
+            "mold",
+            ra.map(|r| r.mold.to_string()),
+            rb.map(|r| r.mold.to_string()),
+        );
+
+        let (ia, ib) = (a.infra.as_ref(), b.infra.as_ref());
+        field(
+            "instance_size",
+            ia.and_then(|i| i.instance_size.as_ref())
+                .map(|s| s.to_string()),
+            ib.and_then(|i| i.instance_size.as_ref())
+                .map(|s| s.to_string()),
+        );
+        field(
+            "replicas",
+            ia.and_then(|i| i.replicas).map(|r| r.to_string()),
+            ib.and_then(|i| i.replicas).map(|r| r.to_string()),
+        );
+
+        // Secret keys can only be compared if both deployments know about them
+        let (secrets_added, secrets_removed) = match (&a.secret_keys, &b.secret_keys) {
+            (Some(ka), Some(kb)) => {
+                let ka = ka.iter().collect::<BTreeSet<_>>();
+                let kb = kb.iter().collect::<BTreeSet<_>>();
+                (
+                    kb.difference(&ka).map(|k| k.to_string()).collect(),
+                    ka.difference(&kb).map(|k| k.to_string()).collect(),
+                )
+            }
+            _ => Default::default(),
+        };
+
+        Self {
+            a: a.id.clone(),
+            b: b.id.clone(),
+            changes,
+            secrets_added,
+            secrets_removed,
+            commits: None,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty() && self.secrets_added.is_empty() && self.secrets_removed.is_empty()
+    }
+
+    pub fn to_string_colored(&self) -> String {
+        let mut out = format!(
+            "Comparing deployment {} to {}\n",
+            self.a.as_str().bold(),
+            self.b.as_str().bold()
+        );
+
+        if self.is_empty() {
+            out.push_str(
+                "No differences in build metadata, build arguments, infra or secret keys\n",
+            );
+        }
+        for change in &self.changes {
+            let value = |v: &Option<String>| v.clone().unwrap_or_else(|| "<none>".to_owned());
+            out.push_str(&format!(
+                "  {}: {} -> {}\n",
+                change.field,
+                value(&change.a).red(),
+                value(&change.b).green(),
+            ));
+        }
+        if !self.secrets_added.is_empty() || !self.secrets_removed.is_empty() {
+            out.push_str("Secret keys:\n");
+            for key in &self.secrets_added {
+                out.push_str(&format!("  {}\n", format!("+ {key}").green()));
+            }
+            for key in &self.secrets_removed {
+                out.push_str(&format!("  {}\n", format!("- {key}").red()));
+            }
+        }
+        if let Some(commits) = &self.commits {
+            out.push_str(&format!("Commits ({}):\n", commits.len()));
+            for commit in commits {
+                out.push_str(&format!(
+                    "  {} {}\n",
+                    commit.id.chars().take(7).collect::<String>().yellow(),
+                    commit.summary.as_deref().unwrap_or_default(),
+                ));
+            }
+        }
+
+        out
+    }
+}
+
+fn rust_build_args(deployment: &DeploymentResponse) -> Option<&BuildArgsRust> {
+    match deployment.build_args {
+        Some(BuildArgs::Rust(ref r)) => Some(r),
+        None => None,
+    }
+}
+
+/// Commits that are reachable from `to` but not from `from`, newest first
+pub fn commit_range(repo: &Repository, from: &str, to: &str) -> Result<Vec<Commit>> {
+    let mut walk = repo.revwalk()?;
+    walk.push(Oid::from_str(to)?)?;
+    walk.hide(Oid::from_str(from)?)?;
+
+    walk.map(|oid| {
+        let commit = repo.find_commit(oid?)?;
+        Ok(Commit {
+            id: commit.id().to_string(),
+            summary: commit.summary().map(ToOwned::to_owned),
+        })
+    })
+    .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Utc;
+    use shuttle_common::models::{
+        deployment::{BuildMeta, DeploymentState},
+        infra::InfraRequest,
+    };
+
+    use super::*;
+
+    fn deployment(id: &str) -> DeploymentResponse {
+        DeploymentResponse {
+            id: id.to_owned(),
+            state: DeploymentState::Running,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+            uris: vec![],
+            build_id: None,
+            build_meta: Some(BuildMeta {
+                git_commit_id: Some("aaa".to_owned()),
+                git_branch: Some("main".to_owned()),
+                ..Default::default()
+            }),
+            build_args: Some(BuildArgs::Rust(BuildArgsRust::default())),
+            infra: None,
+            secret_keys: Some(vec!["A".to_owned(), "B".to_owned()]),
+        }
+    }
+
+    #[test]
+    fn identical() {
+        let diff = DeploymentDiff::new(&deployment("a"), &deployment("b"));
+        assert!(diff.is_empty());
+    }
+
+    #[test]
+    fn finds_changes() {
+        let a = deployment("a");
+        let mut b = deployment("b");
+        b.build_meta.as_mut().unwrap().git_commit_id = Some("bbb".to_owned());
+        let Some(BuildArgs::Rust(ref mut rust)) = b.build_args else {
+            unreachable!()
+        };
+        rust.features = Some("shuttle".to_owned());
+        rust.mold = true;
+        b.infra = Some(InfraRequest {
+            replicas: Some(2),
+            ..Default::default()
+        });
+        b.secret_keys = Some(vec!["B".to_owned(), "C".to_owned()]);
+
+        let diff = DeploymentDiff::new(&a, &b);
+        assert_eq!(
+            diff.changes,
+            vec![
+                FieldChange {
+                    field: "git_commit_id",
+                    a: Some("aaa".to_owned()),
+                    b: Some("bbb".to_owned()),
+                },
+                FieldChange {
+                    field: "features",
+                    a: None,
+                    b: Some("shuttle".to_owned()),
+                },
+                FieldChange {
+                    field: "mold",
+                    a: Some("false".to_owned()),
+                    b: Some("true".to_owned()),
+                },
+                FieldChange {
+                    field: "replicas",
+                    a: None,
+                    b: Some("2".to_owned()),
+                },
+            ]
+        );
+        assert_eq!(diff.secrets_added, vec!["C".to_owned()]);
+        assert_eq!(diff.secrets_removed, vec!["A".to_owned()]);
+    }
+}
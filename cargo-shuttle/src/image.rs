@@ -0,0 +1,250 @@
+use std::{
+    collections::BTreeMap,
+    fmt::Write,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{bail, Context, Result};
+use bollard::{image::BuildImageOptions, Docker};
+use futures::StreamExt;
+use shuttle_common::models::deployment::BuildArgsRust;
+use tracing::{debug, trace};
+
+use crate::archive::archive_path;
+
+/// Name of the generated Dockerfile in the build context
+pub const DOCKERFILE_NAME: &str = ".shuttle.Dockerfile";
+/// Deployments run on this platform, so images are built for it regardless of the host
+const PLATFORM: &str = "linux/amd64";
+const CHEF_IMAGE: &str = "lukemathwalker/cargo-chef:latest-rust-1";
+const RUST_IMAGE: &str = "rust:1";
+const RUNTIME_IMAGE: &str = "debian:bookworm-slim";
+/// Where the built binary is placed in the runtime image
+const BINARY_PATH: &str = "/usr/local/bin/shuttle-app";
+
+/// Create a Dockerfile that builds the project the same way as the platform would with these build args
+pub fn dockerfile(args: &BuildArgsRust) -> Result<String> {
+    if !args.cargo_build {
+        bail!("Only builds with the built in `cargo build` setup can be built locally");
+    }
+    let Some(binary_name) = args.binary_name.as_deref() else {
+        bail!("No binary to build");
+    };
+
+    let mut cargo_args = String::new();
+    if let Some(package) = args.package_name.as_deref() {
+        write!(cargo_args, " --package {package}")?;
+    }
+    if args.no_default_features {
+        cargo_args.push_str(" --no-default-features");
+    }
+    if let Some(features) = args.features.as_deref() {
+        write!(cargo_args, " --features {features}")?;
+    }
+    let cargo = if args.mold {
+        "mold -run cargo"
+    } else {
+        "cargo"
+    };
+
+    let mut d = String::new();
+    writeln!(
+        d,
+        "FROM {} AS chef",
+        if args.cargo_chef {
+            CHEF_IMAGE
+        } else {
+            RUST_IMAGE
+        }
+    )?;
+    writeln!(d, "WORKDIR /app")?;
+    if args.mold {
+        writeln!(
+            d,
+            "RUN apt-get update && apt-get install -y --no-install-recommends mold && rm -rf /var/lib/apt/lists/*"
+        )?;
+    }
+    writeln!(d)?;
+
+    if args.cargo_chef {
+        writeln!(d, "FROM chef AS planner")?;
+        writeln!(d, "COPY . .")?;
+        writeln!(d, "RUN cargo chef prepare --recipe-path recipe.json")?;
+        writeln!(d)?;
+    }
+
+    writeln!(d, "FROM chef AS builder")?;
+    if args.cargo_chef {
+        writeln!(d, "COPY --from=planner /app/recipe.json recipe.json")?;
+        writeln!(
+            d,
+            "RUN {cargo} chef cook --release --recipe-path recipe.json{cargo_args}"
+        )?;
+    }
+    writeln!(d, "COPY . .")?;
+    writeln!(
+        d,
+        "RUN {cargo} build --release{cargo_args} --bin {binary_name}"
+    )?;
+    writeln!(d, "RUN cp target/release/{binary_name} {BINARY_PATH}")?;
+    writeln!(d)?;
+
+    writeln!(d, "FROM {RUNTIME_IMAGE} AS runtime")?;
+    writeln!(
+        d,
+        "RUN apt-get update && apt-get install -y --no-install-recommends ca-certificates && rm -rf /var/lib/apt/lists/*"
+    )?;
+    writeln!(d, "COPY --from=builder {BINARY_PATH} {BINARY_PATH}")?;
+    writeln!(d, "ENTRYPOINT [\"{BINARY_PATH}\"]")?;
+
+    Ok(d)
+}
+
+/// Pack the build files and the generated Dockerfile into a tar archive to use as the build context
+pub fn build_context(files: BTreeMap<PathBuf, PathBuf>, dockerfile: &str) -> Result<Vec<u8>> {
+    let mut tar = tar::Builder::new(Vec::new());
+    for (path, name) in files {
+        trace!("Packing {path:?}");
+        let file = fs::File::open(&path).with_context(|| format!("reading {}", path.display()))?;
+        let size = file.metadata()?.len();
+        tar.append_data(
+            &mut header(size, file_mode(&path)),
+            archive_path(&name),
+            file,
+        )
+        .with_context(|| format!("packing {}", path.display()))?;
+    }
+    tar.append_data(
+        &mut header(dockerfile.len() as u64, 0o644),
+        DOCKERFILE_NAME,
+        dockerfile.as_bytes(),
+    )?;
+
+    tar.into_inner().context("finishing build context")
+}
+
+/// Header for a regular file. Timestamps and owners are left empty so that the context only depends on the file contents.
+fn header(size: u64, mode: u32) -> tar::Header {
+    let mut header = tar::Header::new_gnu();
+    header.set_entry_type(tar::EntryType::Regular);
+    header.set_size(size);
+    header.set_mode(mode);
+    header
+}
+
+#[cfg(unix)]
+fn file_mode(path: &Path) -> u32 {
+    use std::os::unix::fs::PermissionsExt;
+
+    match fs::metadata(path) {
+        Ok(m) if m.permissions().mode() & 0o111 != 0 => 0o755,
+        _ => 0o644,
+    }
+}
+#[cfg(not(unix))]
+fn file_mode(_path: &Path) -> u32 {
+    0o644
+}
+
+/// Build the image with the Docker daemon, printing the build output to stderr
+pub async fn build_image(docker: &Docker, context: Vec<u8>, tag: &str) -> Result<()> {
+    docker
+        .ping()
+        .await
+        .context("connecting to the Docker daemon. Is Docker running?")?;
+
+    let options = BuildImageOptions {
+        dockerfile: DOCKERFILE_NAME.to_owned(),
+        t: tag.to_owned(),
+        platform: PLATFORM.to_owned(),
+        rm: true,
+        ..Default::default()
+    };
+    debug!(?options, context_size = context.len(), "building image");
+
+    let mut stream = docker.build_image(options, None, Some(context.into()));
+    while let Some(info) = stream.next().await {
+        let info = info.context("building image")?;
+        if let Some(error) = info.error {
+            bail!("Image build failed: {}", error.trim_end());
+        }
+        if let Some(line) = info.stream {
+            eprint!("{line}");
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dockerfile_honours_build_args() {
+        let args = BuildArgsRust {
+            package_name: Some("app".to_owned()),
+            binary_name: Some("server".to_owned()),
+            features: Some("shuttle".to_owned()),
+            no_default_features: true,
+            mold: true,
+            ..Default::default()
+        };
+        let d = dockerfile(&args).unwrap();
+
+        assert!(d.starts_with(&format!("FROM {CHEF_IMAGE} AS chef")));
+        assert!(d.contains(
+            "RUN mold -run cargo chef cook --release --recipe-path recipe.json --package app --no-default-features --features shuttle\n"
+        ));
+        assert!(d.contains(
+            "RUN mold -run cargo build --release --package app --no-default-features --features shuttle --bin server\n"
+        ));
+
+        let args = BuildArgsRust {
+            cargo_chef: false,
+            binary_name: Some("server".to_owned()),
+            ..Default::default()
+        };
+        let d = dockerfile(&args).unwrap();
+
+        assert!(d.starts_with(&format!("FROM {RUST_IMAGE} AS chef")));
+        assert!(!d.contains("chef cook"));
+        assert!(!d.contains("mold"));
+        assert!(d.contains("RUN cargo build --release --bin server\n"));
+    }
+
+    #[test]
+    fn build_context_entries() {
+        let dir = tempfile::tempdir().unwrap();
+        let lib = dir.path().join("lib.rs");
+        let main = dir.path().join("main.rs");
+        fs::write(&lib, "").unwrap();
+        fs::write(&main, "fn main() {}").unwrap();
+        // too long for the name field of a plain ustar header
+        let long = PathBuf::from("a/".repeat(60) + "lib.rs");
+        let files = BTreeMap::from([(lib, long.clone()), (main, PathBuf::from("src/main.rs"))]);
+
+        let context = build_context(files, "FROM scratch\n").unwrap();
+        let mut archive = tar::Archive::new(context.as_slice());
+        let entries = archive
+            .entries()
+            .unwrap()
+            .map(|e| {
+                let mut e = e.unwrap();
+                let mut content = String::new();
+                std::io::Read::read_to_string(&mut e, &mut content).unwrap();
+                (e.path().unwrap().into_owned(), content)
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            entries,
+            vec![
+                (long, String::new()),
+                (PathBuf::from("src/main.rs"), "fn main() {}".to_owned()),
+                (PathBuf::from(DOCKERFILE_NAME), "FROM scratch\n".to_owned()),
+            ]
+        );
+    }
+}
@@ -6,10 +6,10 @@ use std::{
     path::{Path, PathBuf},
 };
 
-use anyhow::{Context, Result};
+use anyhow::{anyhow, Context, Result};
 use gix::clone::PrepareFetch;
 use gix::create::{self, Kind};
-use gix::remote::fetch::Shallow;
+use gix::remote::{fetch::Shallow, Direction};
 use gix::{open, progress};
 use regex::Regex;
 use shuttle_common::constants::EXAMPLES_README;
@@ -168,6 +168,70 @@ fn gix_clone(from_url: &str, to_path: &Path) -> Result<()> {
     Ok(())
 }
 
+/// A branch, tag or commit hash resolved in a remote Git repository
+#[derive(Debug, PartialEq, Eq)]
+pub struct ResolvedRev {
+    pub commit: String,
+    /// The branch name, if the rev resolved to a branch rather than a tag or commit hash
+    pub branch: Option<String>,
+}
+
+/// Like `git ls-remote`, find the commit that the branch or tag `rev` points to
+/// in the Git repository found at `from_url`, without fetching any objects.
+/// Fails if the remote has no such ref.
+/// A full commit hash is returned as is, since servers only advertise refs.
+pub fn gix_resolve_rev(from_url: &str, rev: &str) -> Result<ResolvedRev> {
+    if is_commit_hash(rev) {
+        return Ok(ResolvedRev {
+            commit: rev.to_ascii_lowercase(),
+            branch: None,
+        });
+    }
+
+    // A remote can only be used from a repository, so an empty one is made to list the refs with
+    let dir = Builder::new()
+        .prefix("cargo-shuttle-ls-remote-")
+        .tempdir()
+        .context("Failed to create temporary directory")?;
+    let repo = gix::init_bare(dir.path()).context("Failed to create temporary repository")?;
+
+    let branch = format!("refs/heads/{rev}");
+    let tag = format!("refs/tags/{rev}");
+    let remote = repo
+        .remote_at(from_url)
+        .with_context(|| format!("'{from_url}' is not a valid repository URL"))?
+        .with_fetch_tags(gix::remote::fetch::Tags::None)
+        .with_refspecs([branch.as_str(), tag.as_str()], Direction::Fetch)
+        .with_context(|| format!("'{rev}' is not a valid branch or tag name"))?;
+    let (ref_map, _handshake) = remote
+        .connect(Direction::Fetch)
+        .with_context(|| format!("Failed to connect to repository '{from_url}'"))?
+        .ref_map(progress::Discard, Default::default())
+        .with_context(|| format!("Failed to list branches and tags of '{from_url}'"))?;
+
+    // A branch takes precedence over a tag with the same name, like in `git clone --branch`
+    [(branch, true), (tag, false)]
+        .iter()
+        .find_map(|(name, is_branch)| {
+            ref_map.remote_refs.iter().find_map(|r| match r.unpack() {
+                // annotated tags are peeled to the commit they point to
+                (ref_name, Some(object), peeled) if ref_name == name.as_str() => {
+                    Some(ResolvedRev {
+                        commit: peeled.unwrap_or(object).to_string(),
+                        branch: is_branch.then(|| rev.to_owned()),
+                    })
+                }
+                _ => None,
+            })
+        })
+        .ok_or_else(|| anyhow!("No branch or tag named '{rev}' found in '{from_url}'"))
+}
+
+/// Whether `rev` is a full SHA-1 or SHA-256 commit hash
+fn is_commit_hash(rev: &str) -> bool {
+    matches!(rev.len(), 40 | 64) && rev.chars().all(|c| c.is_ascii_hexdigit())
+}
+
 /// Recursively copy all files and directories from `src` to `dest`. If
 /// `git_policy` is set to `Ignore`, the `.git` directory is not copied.
 /// If `git_policy` is set to `Copy`, then the `.git` directory is copied.
@@ -312,6 +376,68 @@ mod tests {
         temp_dir.close().unwrap();
     }
 
+    #[test]
+    fn gix_resolve_rev_lists_refs() {
+        let dir = Builder::new()
+            .prefix("shuttle-ls-remote-test")
+            .tempdir()
+            .unwrap();
+        let git = |args: &[&str]| {
+            let output = std::process::Command::new("git")
+                .args(["-c", "user.name=test", "-c", "user.email=test@example.com"])
+                .args(args)
+                .current_dir(dir.path())
+                .output()
+                .unwrap();
+            assert!(output.status.success(), "git {args:?} failed");
+            String::from_utf8(output.stdout).unwrap().trim().to_owned()
+        };
+        git(&["init", "--initial-branch", "main"]);
+        git(&["commit", "--allow-empty", "-m", "first"]);
+        git(&["tag", "-a", "v1", "-m", "release"]);
+        let first = git(&["rev-parse", "HEAD"]);
+        git(&["commit", "--allow-empty", "-m", "second"]);
+        let second = git(&["rev-parse", "HEAD"]);
+
+        let url = format!("file://{}", dir.path().display());
+        assert_eq!(
+            gix_resolve_rev(&url, "main").unwrap(),
+            ResolvedRev {
+                commit: second,
+                branch: Some("main".to_owned())
+            }
+        );
+        // annotated tags resolve to the commit
+        assert_eq!(
+            gix_resolve_rev(&url, "v1").unwrap(),
+            ResolvedRev {
+                commit: first.clone(),
+                branch: None
+            }
+        );
+        assert_eq!(
+            gix_resolve_rev(&url, "v2").unwrap_err().to_string(),
+            format!("No branch or tag named 'v2' found in '{url}'")
+        );
+        // commit hashes are not looked up
+        assert_eq!(
+            gix_resolve_rev(&url, &first.to_uppercase()).unwrap(),
+            ResolvedRev {
+                commit: first,
+                branch: None
+            }
+        );
+    }
+
+    #[test]
+    fn commit_hashes() {
+        assert!(is_commit_hash("0123456789abcdef0123456789abcdef01234567"));
+        assert!(is_commit_hash(&"a".repeat(64)));
+        assert!(!is_commit_hash("0123456"));
+        assert!(!is_commit_hash("main"));
+        assert!(!is_commit_hash(&"g".repeat(40)));
+    }
+
     #[test]
     fn copy_dirs_works() {
         let temp_dir = Builder::new()
@@ -1,8 +1,14 @@
+mod archive;
 mod args;
 pub mod builder;
 pub mod config;
+mod diff;
+mod image;
 mod init;
+mod logs;
 mod provisioner_server;
+mod registry_auth;
+mod secrets;
 mod util;
 
 use std::collections::{BTreeMap, HashMap};
@@ -21,9 +27,6 @@ use crossterm::style::Stylize;
 use dialoguer::{theme::ColorfulTheme, Confirm, Input, Password, Select};
 use futures::{SinkExt, StreamExt};
 use git2::Repository;
-use globset::{Glob, GlobSetBuilder};
-use ignore::overrides::OverrideBuilder;
-use ignore::WalkBuilder;
 use indicatif::ProgressBar;
 use indoc::formatdoc;
 use reqwest::header::HeaderMap;
@@ -37,15 +40,20 @@ use shuttle_common::{
         auth::{KeyMessage, TokenMessage},
         deployment::{
             BuildArgs, BuildArgsRust, BuildMeta, DeploymentRequest, DeploymentRequestBuildArchive,
-            DeploymentRequestImage, DeploymentResponse, DeploymentState, Environment,
-            GIT_STRINGS_MAX_LENGTH,
+            DeploymentRequestGitRepo, DeploymentRequestImage, DeploymentResponse, DeploymentState,
+            Environment, RegistryCredentials, GIT_STRINGS_MAX_LENGTH,
         },
         error::ApiError,
-        log::LogItem,
+        infra::InfraRequest,
+        log::{LogItem, LogsResponse},
         project::ProjectUpdateRequest,
-        resource::ResourceType,
+        resource::{ResourceResponse, ResourceType, SecretsUpdateRequest},
+    },
+    secrets::{Secret, SecretStore},
+    tables::{
+        deployments_table, get_certificates_table, get_projects_table, get_resource_tables,
+        get_secret_values_table,
     },
-    tables::{deployments_table, get_certificates_table, get_projects_table, get_resource_tables},
 };
 use shuttle_ifc::parse_infra_from_code;
 use strum::{EnumMessage, VariantArray};
@@ -55,24 +63,40 @@ use tokio_tungstenite::tungstenite::Message;
 use tracing::{debug, error, info, trace, warn};
 use tracing_subscriber::{fmt, prelude::*, registry, EnvFilter};
 use zip::write::FileOptions;
+use zip::CompressionMethod;
 
+use crate::archive::{
+    archive_path, build_manifest, format_size, gather_build_files, largest_files, ArchiveReport,
+    BlobCache,
+};
 use crate::args::{
-    CertificateCommand, ConfirmationArgs, DeployArgs, DeploymentCommand, GenerateCommand, InitArgs,
-    LoginArgs, LogoutArgs, LogsArgs, McpCommand, OutputMode, ProjectCommand, ProjectUpdateCommand,
-    ResourceCommand, SecretsArgs, TableArgs, TemplateLocation,
+    CargoBuildArgs, CertificateCommand, ConfirmationArgs, DeployArgs, DeploymentCommand,
+    GenerateCommand, ImageBuildArgs, InitArgs, LocalCommand, LogFilterArgs, LoginArgs, LogoutArgs,
+    LogsArgs, McpCommand, OutputMode, ProjectCommand, ProjectUpdateCommand, ResourceCommand,
+    SecretsArgs, SecretsCommand, TableArgs, TemplateLocation,
 };
 pub use crate::args::{Command, ProjectArgs, RunArgs, ShuttleArgs};
 use crate::builder::{
-    async_cargo_metadata, build_workspace, find_first_shuttle_package, BuiltService,
+    async_cargo_metadata, build_workspace, find_shuttle_package, find_shuttle_packages,
+    BuiltService, CargoBuildOptions,
 };
-use crate::config::RequestContext;
-use crate::provisioner_server::{ProvApiState, ProvisionerServer};
+use crate::config::{ArchiveCompression, ProjectBuildConfig, RequestContext};
+use crate::diff::{commit_range, DeploymentDiff};
+use crate::logs::{LogDeduplicator, LogFormat, LogsRange};
+use crate::provisioner_server::{
+    connect_docker, get_local_containers_table, LocalProvisioner, ProvApiState, ProvisionerServer,
+};
+use crate::registry_auth::{docker_config_credentials, registry_of};
+use crate::secrets::parse_secrets_file;
 use crate::util::{
     bacon, check_and_warn_runtime_version, generate_completions, generate_manpage,
     get_templates_schema, is_dirty, open_gh_issue, read_ws_until_text, update_cargo_shuttle,
+    watch::watch_files,
 };
 
 const VERSION: &str = env!("CARGO_PKG_VERSION");
+/// Default size above which files in the deployment archive are warned about
+const DEFAULT_LARGE_FILE_WARNING: u64 = 10 * 1024 * 1024;
 
 /// Returns the args and whether the PATH arg of the init command was explicitly given
 pub fn parse_args() -> (ShuttleArgs, bool) {
@@ -132,6 +156,7 @@ pub struct Shuttle {
     ctx: RequestContext,
     client: Option<ShuttleApiClient>,
     output_mode: OutputMode,
+    log_format: LogFormat,
     /// Alter behaviour based on which CLI is used
     bin: Binary,
 }
@@ -148,31 +173,44 @@ impl Shuttle {
             ctx,
             client: None,
             output_mode: OutputMode::Normal,
+            log_format: LogFormat::Text,
             bin,
         })
     }
 
     pub async fn run(mut self, args: ShuttleArgs, provided_path_to_init: bool) -> Result<()> {
-        if matches!(args.cmd, Command::Resource(ResourceCommand::Dump { .. })) {
-            bail!("This command is not yet supported on the NEW platform (shuttle.dev).");
+        if matches!(
+            args.cmd,
+            Command::Resource(
+                ResourceCommand::Dump { local: false, .. }
+                    | ResourceCommand::Restore { local: false, .. }
+            )
+        ) {
+            bail!("This command is not yet supported on the NEW platform (shuttle.dev). Use `--local` to target the database provisioned by `shuttle run`.");
         }
 
-        self.output_mode = args.output_mode;
+        (self.log_format, self.output_mode) = LogFormat::split_output_format(args.output_mode);
+
+        // Resource commands with `--local` act on the containers from `shuttle run` and don't need the API
+        let local_resource_cmd = matches!(&args.cmd, Command::Resource(cmd) if cmd.is_local());
 
         // Set up the API client for all commands that call the API
-        if matches!(
-            args.cmd,
-            Command::Init(..)
-                | Command::Deploy(..)
-                | Command::Logs { .. }
-                | Command::Account
-                | Command::Login(..)
-                | Command::Logout(..)
-                | Command::Deployment(..)
-                | Command::Resource(..)
-                | Command::Certificate(..)
-                | Command::Project(..)
-        ) {
+        if !local_resource_cmd
+            && matches!(
+                args.cmd,
+                Command::Init(..)
+                    | Command::Deploy(..)
+                    | Command::Logs { .. }
+                    | Command::Account
+                    | Command::Login(..)
+                    | Command::Logout(..)
+                    | Command::Deployment(..)
+                    | Command::Resource(..)
+                    | Command::Secrets(..)
+                    | Command::Certificate(..)
+                    | Command::Project(..)
+            )
+        {
             let api_url = args
                 .api_url
                 // calculate env-specific url if no explicit url given but an env was given
@@ -208,22 +246,26 @@ impl Shuttle {
         }
 
         // Load project context for all commands that need to know which project is being targetted
-        if matches!(
-            args.cmd,
-            Command::Deploy(..)
-                | Command::Deployment(..)
-                | Command::Resource(..)
-                | Command::Certificate(..)
-                | Command::Project(
-                    // ProjectCommand::List does not need to know which project we are in
-                    ProjectCommand::Create
-                        | ProjectCommand::Update(..)
-                        | ProjectCommand::Status
-                        | ProjectCommand::Delete { .. }
-                        | ProjectCommand::Link
-                )
-                | Command::Logs { .. }
-        ) {
+        if !local_resource_cmd
+            && matches!(
+                args.cmd,
+                // A dry run only looks at local files
+                Command::Deploy(DeployArgs { dry_run: false, .. })
+                    | Command::Deployment(..)
+                    | Command::Resource(..)
+                    | Command::Secrets(..)
+                    | Command::Certificate(..)
+                    | Command::Project(
+                        // ProjectCommand::List does not need to know which project we are in
+                        ProjectCommand::Create
+                            | ProjectCommand::Update(..)
+                            | ProjectCommand::Status
+                            | ProjectCommand::Delete { .. }
+                            | ProjectCommand::Link
+                    )
+                    | Command::Logs { .. }
+            )
+        {
             // Command::Run only uses `load_local_config` (below) instead of `load_project` since it does not target a project in the API
             self.load_project(
                 &args.project_args,
@@ -259,7 +301,19 @@ impl Shuttle {
                 self.ctx.load_local_config(&args.project_args)?;
                 self.local_run(run_args, args.debug).await
             }
+            Command::Deploy(deploy_args) if deploy_args.dry_run => {
+                self.ctx.load_local_config(&args.project_args)?;
+                self.deploy_dry_run()
+            }
             Command::Deploy(deploy_args) => self.deploy(deploy_args).await,
+            Command::Build(build_args) => {
+                self.ctx.load_local_config(&args.project_args)?;
+                self.build_image(build_args).await
+            }
+            Command::Local(cmd) => {
+                self.ctx.load_local_config(&args.project_args)?;
+                self.local(cmd).await
+            }
             Command::Logs(logs_args) => self.logs(logs_args).await,
             Command::Deployment(cmd) => match cmd {
                 DeploymentCommand::List { page, limit, table } => {
@@ -269,6 +323,10 @@ impl Shuttle {
                 DeploymentCommand::Redeploy { id, tracking_args } => {
                     self.deployment_redeploy(id, tracking_args).await
                 }
+                DeploymentCommand::Rollback { tracking_args } => {
+                    self.deployment_rollback(tracking_args).await
+                }
+                DeploymentCommand::Diff { a, b } => self.deployment_diff(a, b).await,
                 DeploymentCommand::Stop { tracking_args } => self.stop(tracking_args).await,
             },
             Command::Resource(cmd) => match cmd {
@@ -280,7 +338,47 @@ impl Shuttle {
                     resource_type,
                     confirmation: ConfirmationArgs { yes },
                 } => self.resource_delete(&resource_type, yes).await,
-                ResourceCommand::Dump { resource_type } => self.resource_dump(&resource_type).await,
+                ResourceCommand::Dump {
+                    resource_type,
+                    database,
+                    output,
+                    ..
+                } => {
+                    self.ctx.load_local_config(&args.project_args)?;
+                    self.resource_dump_local(resource_type, database, output)
+                        .await
+                }
+                ResourceCommand::Restore {
+                    resource_type,
+                    database,
+                    input,
+                    confirmation: ConfirmationArgs { yes },
+                    ..
+                } => {
+                    self.ctx.load_local_config(&args.project_args)?;
+                    self.resource_restore_local(resource_type, database, input, yes)
+                        .await
+                }
+                ResourceCommand::Reset {
+                    resource_type,
+                    local,
+                    confirmation: ConfirmationArgs { yes },
+                } => {
+                    if !local {
+                        bail!("Resetting a resource is only supported locally. Use `--local` to reset the resource provisioned by `shuttle run`.");
+                    }
+                    self.ctx.load_local_config(&args.project_args)?;
+                    self.resource_reset_local(resource_type, yes).await
+                }
+            },
+            Command::Secrets(cmd) => match cmd {
+                SecretsCommand::List { show_values, table } => {
+                    self.secrets_list(table, show_values).await
+                }
+                SecretsCommand::Set { secrets } => self.secrets_set(secrets).await,
+                SecretsCommand::Unset { keys } => self.secrets_unset(keys).await,
+                SecretsCommand::Import { file } => self.secrets_import(&file).await,
+                SecretsCommand::Export => self.secrets_export().await,
             },
             Command::Certificate(cmd) => match cmd {
                 CertificateCommand::Add { domain } => self.add_certificate(domain).await,
@@ -991,63 +1089,153 @@ impl Shuttle {
     }
 
     async fn logs(&self, args: LogsArgs) -> Result<()> {
-        if args.follow {
-            eprintln!("Streamed logs are not yet supported on the shuttle.dev platform.");
-            return Ok(());
-        }
-        if args.tail.is_some() | args.head.is_some() {
-            eprintln!("Fetching log ranges are not yet supported on the shuttle.dev platform.");
-            return Ok(());
-        }
         let client = self.client.as_ref().unwrap();
         let pid = self.ctx.project_id();
-        let r = if args.all_deployments {
-            client.get_project_logs(pid).await?
+        let deployment_id = if args.all_deployments {
+            None
+        } else if args.latest {
+            // Find latest deployment (not always an active one)
+            let deployments = client
+                .get_deployments(pid, 1, 1)
+                .await?
+                .into_inner()
+                .deployments;
+            let Some(most_recent) = deployments.into_iter().next() else {
+                println!("No deployments found");
+                return Ok(());
+            };
+            eprintln!("Getting logs from: {}", most_recent.id);
+            Some(most_recent.id)
+        } else if let Some(id) = args.id.clone() {
+            Some(id)
         } else {
-            let id = if args.latest {
-                // Find latest deployment (not always an active one)
-                let deployments = client
-                    .get_deployments(pid, 1, 1)
-                    .await?
-                    .into_inner()
-                    .deployments;
-                let Some(most_recent) = deployments.into_iter().next() else {
-                    println!("No deployments found");
-                    return Ok(());
-                };
-                eprintln!("Getting logs from: {}", most_recent.id);
-                most_recent.id
-            } else if let Some(id) = args.id {
-                id
-            } else {
-                let Some(current) = client.get_current_deployment(pid).await?.into_inner() else {
-                    println!("No deployments found");
-                    return Ok(());
-                };
-                eprintln!("Getting logs from: {}", current.id);
-                current.id
+            let Some(current) = client.get_current_deployment(pid).await?.into_inner() else {
+                println!("No deployments found");
+                return Ok(());
             };
-            client.get_deployment_logs(pid, &id).await?
+            eprintln!("Getting logs from: {}", current.id);
+            Some(current.id)
+        };
+
+        let r = match deployment_id {
+            Some(ref id) => client.get_deployment_logs(pid, id).await?,
+            None => client.get_project_logs(pid).await?,
         };
+        let range = LogsRange::from(&args);
+        let (logs, raw_json) = r.into_parts();
+        let logs = logs
+            .logs
+            .into_iter()
+            .filter(|log| args.filter.matches(log))
+            .collect::<Vec<_>>();
+        let logs = range.apply(logs);
+        let mut dedup = LogDeduplicator::default();
+
         match self.output_mode {
-            OutputMode::Normal => {
-                let logs = r.into_inner().logs;
+            // keep the raw response when it is not altered
+            OutputMode::Json
+                if !args.follow
+                    && !self.log_format.is_structured()
+                    && range == LogsRange::All
+                    && args.filter.is_empty() =>
+            {
+                println!("{raw_json}");
+                return Ok(());
+            }
+            OutputMode::Json if !args.follow && !self.log_format.is_structured() => {
+                println!("{}", serde_json::to_string(&LogsResponse { logs }).unwrap());
+                return Ok(());
+            }
+            _ => {
                 for log in logs {
-                    if args.raw {
-                        println!("{}", log.line);
-                    } else {
-                        println!("{log}");
-                    }
+                    dedup.is_new(&log);
+                    self.print_log_item(&log, args.raw);
                 }
             }
-            OutputMode::Json => {
-                println!("{}", r.raw_json);
-            }
+        }
+
+        if args.follow {
+            self.follow_logs(pid, deployment_id.as_deref(), &args.filter, dedup, args.raw)
+                .await?;
         }
 
         Ok(())
     }
 
+    /// Stream new log lines over a websocket until interrupted, reconnecting when the connection drops
+    async fn follow_logs(
+        &self,
+        pid: &str,
+        deployment_id: Option<&str>,
+        filter: &LogFilterArgs,
+        mut dedup: LogDeduplicator,
+        raw: bool,
+    ) -> Result<()> {
+        const MAX_CONNECT_ATTEMPTS: u32 = 5;
+
+        let client = self.client.as_ref().unwrap();
+        let mut failed_attempts = 0;
+        loop {
+            let ws = match deployment_id {
+                Some(id) => client.get_deployment_logs_ws(pid, id).await,
+                None => client.get_project_logs_ws(pid).await,
+            };
+            let ws = match ws {
+                Ok(ws) => {
+                    failed_attempts = 0;
+                    ws
+                }
+                Err(e) => {
+                    failed_attempts += 1;
+                    if failed_attempts >= MAX_CONNECT_ATTEMPTS {
+                        return Err(e.context("connecting to the log stream"));
+                    }
+                    warn!(error = ?e, "failed to connect to log stream");
+                    sleep(Duration::from_secs(failed_attempts as u64)).await;
+                    continue;
+                }
+            };
+            let (mut tx, mut rx) = ws.split();
+
+            // keep the socket alive with ping/pong
+            let pinger = tokio::spawn(async move {
+                loop {
+                    if let Err(e) = tx.send(Message::Ping(Default::default())).await {
+                        trace!(error = %e, "Error when pinging websocket");
+                        break;
+                    };
+                    sleep(Duration::from_secs(20)).await;
+                }
+            });
+
+            while let Some(text) = read_ws_until_text(&mut rx).await? {
+                let log = match serde_json::from_str::<LogItem>(&text) {
+                    Ok(log) => log,
+                    Err(e) => {
+                        warn!(error = %e, "received invalid log item");
+                        continue;
+                    }
+                };
+                if filter.matches(&log) && dedup.is_new(&log) {
+                    self.print_log_item(&log, raw);
+                }
+            }
+            pinger.abort();
+
+            eprintln!("{}", "Log stream disconnected, reconnecting...".yellow());
+            sleep(Duration::from_secs(1)).await;
+        }
+    }
+
+    fn print_log_item(&self, log: &LogItem, raw: bool) {
+        // streamed logs can't be wrapped in a single JSON document
+        let format = match (self.log_format, &self.output_mode) {
+            (LogFormat::Text, OutputMode::Json) => LogFormat::Ndjson,
+            (format, _) => format,
+        };
+        println!("{}", format.format(log, raw));
+    }
+
     async fn deployments_list(&self, page: u32, limit: u32, table_args: TableArgs) -> Result<()> {
         let client = self.client.as_ref().unwrap();
         if limit == 0 {
@@ -1122,6 +1310,125 @@ impl Shuttle {
         Ok(())
     }
 
+    async fn deployment_diff(&self, a: String, b: Option<String>) -> Result<()> {
+        let client = self.client.as_ref().unwrap();
+        let pid = self.ctx.project_id();
+
+        let a = client.get_deployment(pid, &a).await?.into_inner();
+        let b = match b {
+            Some(id) => client.get_deployment(pid, &id).await?.into_inner(),
+            None => {
+                let Some(d) = client.get_current_deployment(pid).await?.into_inner() else {
+                    println!("No deployment found");
+                    return Ok(());
+                };
+                d
+            }
+        };
+
+        let mut diff = DeploymentDiff::new(&a, &b);
+        let commit_id =
+            |d: &DeploymentResponse| d.build_meta.as_ref().and_then(|m| m.git_commit_id.clone());
+        if let (Some(from), Some(to)) = (commit_id(&a), commit_id(&b)) {
+            if let Ok(repo) = Repository::discover(self.ctx.project_directory()) {
+                diff.commits = commit_range(&repo, &from, &to)
+                    .inspect_err(
+                        |e| debug!(error = ?e, "could not get commit range between deployments"),
+                    )
+                    .ok();
+            }
+        }
+
+        match self.output_mode {
+            OutputMode::Normal => {
+                print!("{}", diff.to_string_colored());
+            }
+            OutputMode::Json => {
+                println!("{}", serde_json::to_string(&diff).unwrap());
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn deployment_rollback(&self, tracking_args: DeploymentTrackingArgs) -> Result<()> {
+        let client = self.client.as_ref().unwrap();
+        let pid = self.ctx.project_id();
+
+        let Some(current) = client.get_current_deployment(pid).await?.into_inner() else {
+            println!("No deployment found");
+            return Ok(());
+        };
+
+        self.rollback_from(pid, &current.id, &tracking_args).await
+    }
+
+    /// Redeploy the most recent deployment before `from_id` that reached the running state
+    async fn rollback_from(
+        &self,
+        pid: &str,
+        from_id: &str,
+        tracking_args: &DeploymentTrackingArgs,
+    ) -> Result<()> {
+        let client = self.client.as_ref().unwrap();
+
+        let Some(target) = self.find_last_healthy_deployment(pid, from_id).await? else {
+            bail!("No other deployment that reached the running state was found");
+        };
+        let current = client.get_current_deployment(pid).await?.into_inner();
+        if is_serving(&target, current.as_ref()) {
+            eprintln!(
+                "Deployment {} is still running, no rollback needed",
+                target.id
+            );
+            return Ok(());
+        }
+
+        eprintln!("Rolling back to deployment {}", target.id.as_str().bold());
+        let (deployment, raw_json) = client.redeploy(pid, &target.id).await?.into_parts();
+
+        if tracking_args.no_follow {
+            self.print_deployment(&deployment, &raw_json);
+            return Ok(());
+        }
+
+        self.track_deployment_status_and_print_logs_on_fail(pid, &deployment.id, tracking_args.raw)
+            .await?;
+        eprintln!(
+            "{}",
+            format!("Restored deployment {} as {}", target.id, deployment.id).green()
+        );
+
+        Ok(())
+    }
+
+    /// Find the most recent deployment that reached the running state, skipping `exclude_id`.
+    async fn find_last_healthy_deployment(
+        &self,
+        pid: &str,
+        exclude_id: &str,
+    ) -> Result<Option<DeploymentResponse>> {
+        const PER_PAGE: i32 = 20;
+        const MAX_PAGES: i32 = 5;
+
+        let client = self.client.as_ref().unwrap();
+        for page in 1..=MAX_PAGES {
+            let deployments = client
+                .get_deployments(pid, page, PER_PAGE)
+                .await?
+                .into_inner()
+                .deployments;
+            let last_page = deployments.len() < PER_PAGE as usize;
+
+            let healthy = last_healthy_deployment(deployments, exclude_id);
+            if healthy.is_some() || last_page {
+                return Ok(healthy);
+            }
+        }
+
+        Ok(None)
+    }
+
     async fn deployment_redeploy(
         &self,
         deployment_id: Option<String>,
@@ -1144,14 +1451,7 @@ impl Shuttle {
         let (deployment, raw_json) = client.redeploy(pid, &deployment_id).await?.into_parts();
 
         if tracking_args.no_follow {
-            match self.output_mode {
-                OutputMode::Normal => {
-                    println!("{}", deployment.to_string_colored());
-                }
-                OutputMode::Json => {
-                    println!("{}", raw_json);
-                }
-            }
+            self.print_deployment(&deployment, &raw_json);
             return Ok(());
         }
 
@@ -1182,101 +1482,396 @@ impl Shuttle {
         Ok(())
     }
 
-    async fn resource_delete(&self, resource_type: &ResourceType, no_confirm: bool) -> Result<()> {
+    async fn secrets_list(&self, table_args: TableArgs, show_values: bool) -> Result<()> {
         let client = self.client.as_ref().unwrap();
+        let r = client.get_secrets(self.ctx.project_id()).await?;
+        let secrets = secret_store(r.into_inner())?;
 
-        if !no_confirm {
-            eprintln!(
-                "{}",
-                formatdoc!(
-                    "
-                WARNING:
-                    Are you sure you want to delete this project's {}?
-                    This action is permanent.",
-                    resource_type
-                )
-                .bold()
-                .red()
-            );
-            if !Confirm::with_theme(&ColorfulTheme::default())
-                .with_prompt("Are you sure?")
-                .default(false)
-                .interact()
-                .unwrap()
-            {
-                return Ok(());
+        match self.output_mode {
+            OutputMode::Normal => {
+                if secrets.is_empty() {
+                    println!("No secrets are set for this project.");
+                } else {
+                    let table = get_secret_values_table(&secrets, table_args.raw, show_values);
+                    println!("{table}");
+                }
+            }
+            OutputMode::Json => {
+                let secrets = secrets
+                    .into_iter()
+                    .map(|(k, v)| {
+                        let v = if show_values {
+                            v
+                        } else {
+                            Secret::new(v).redacted().to_owned()
+                        };
+                        (k, v)
+                    })
+                    .collect::<BTreeMap<_, _>>();
+                println!("{}", serde_json::to_string(&secrets).unwrap());
             }
         }
 
-        let msg = client
-            .delete_service_resource(self.ctx.project_id(), resource_type)
-            .await?
-            .into_inner();
-        println!("{msg}");
+        Ok(())
+    }
 
-        eprintln!(
-            "{}",
-            formatdoc! {"
-                Note:
-                    Remember to remove the resource annotation from your #[shuttle_runtime::main] function.
-                    Otherwise, it will be provisioned again during the next deployment."
+    async fn secrets_set(&self, secrets: Vec<String>) -> Result<()> {
+        let mut parsed = HashMap::new();
+        for secret in secrets {
+            let (key, value) = match secret.split_once('=') {
+                Some((key, value)) => (key.to_owned(), value.to_owned()),
+                None => {
+                    let value = Password::with_theme(&ColorfulTheme::default())
+                        .with_prompt(format!("Value for {secret}"))
+                        .allow_empty_password(true)
+                        .interact()?;
+                    (secret, value)
+                }
+            };
+            if key.is_empty() {
+                bail!("Secret keys can't be empty");
             }
-            .yellow(),
-        );
+            parsed.insert(key, value);
+        }
 
-        Ok(())
+        self.secrets_update(parsed, Vec::new()).await
     }
 
-    async fn resource_dump(&self, _resource_type: &ResourceType) -> Result<()> {
-        unimplemented!();
-        // let client = self.client.as_ref().unwrap();
-        // let bytes = client...;
-        // std::io::stdout().write_all(&bytes).unwrap();
-        // Ok(())
+    async fn secrets_unset(&self, keys: Vec<String>) -> Result<()> {
+        self.secrets_update(HashMap::new(), keys).await
     }
 
-    async fn list_certificates(&self, table_args: TableArgs) -> Result<()> {
+    async fn secrets_import(&self, file: &Path) -> Result<()> {
+        let secrets = Shuttle::read_secrets_file(file)?;
+        if secrets.is_empty() {
+            bail!("No secrets found in {}", file.display());
+        }
+
+        self.secrets_update(secrets, Vec::new()).await
+    }
+
+    async fn secrets_update(&self, set: HashMap<String, String>, unset: Vec<String>) -> Result<()> {
         let client = self.client.as_ref().unwrap();
-        let r = client.list_certificates(self.ctx.project_id()).await?;
+        let pid = self.ctx.project_id();
+        let (set_count, unset_count) = (set.len(), unset.len());
+
+        let r = client
+            .update_secrets(pid, SecretsUpdateRequest { set, unset })
+            .await?;
 
         match self.output_mode {
             OutputMode::Normal => {
-                let table =
-                    get_certificates_table(r.into_inner().certificates.as_ref(), table_args.raw);
-                println!("{table}");
+                if set_count > 0 {
+                    println!("Set {set_count} secret(s)");
+                }
+                if unset_count > 0 {
+                    println!("Removed {unset_count} secret(s)");
+                }
+                eprintln!(
+                    "{}",
+                    "Note: Running deployments pick up the new secrets on their next deployment."
+                        .yellow()
+                );
             }
             OutputMode::Json => {
-                println!("{}", r.raw_json);
+                let secrets = secret_store(r.into_inner())?;
+                let keys = secrets.into_iter().map(|(k, _)| k).collect::<Vec<_>>();
+                println!("{}", serde_json::to_string(&keys).unwrap());
             }
         }
 
         Ok(())
     }
-    async fn add_certificate(&self, domain: String) -> Result<()> {
+
+    async fn secrets_export(&self) -> Result<()> {
         let client = self.client.as_ref().unwrap();
-        let r = client
-            .add_certificate(self.ctx.project_id(), domain.clone())
-            .await?;
+        let r = client.get_secrets(self.ctx.project_id()).await?;
+        let secrets = secret_store(r.into_inner())?
+            .into_iter()
+            .collect::<BTreeMap<_, _>>();
 
         match self.output_mode {
             OutputMode::Normal => {
-                println!("Added certificate for {}", r.into_inner().subject);
+                print!(
+                    "{}",
+                    toml::to_string(&secrets).context("serializing secrets")?
+                );
             }
             OutputMode::Json => {
-                println!("{}", r.raw_json);
+                println!("{}", serde_json::to_string(&secrets).unwrap());
             }
         }
 
         Ok(())
     }
-    async fn delete_certificate(&self, domain: String, no_confirm: bool) -> Result<()> {
-        let client = self.client.as_ref().unwrap();
 
-        if !no_confirm {
-            eprintln!(
-                "{}",
-                formatdoc!(
-                    "
+    async fn local(&self, cmd: LocalCommand) -> Result<()> {
+        let prov = LocalProvisioner::new(self.ctx.local_config())?;
+        let project_name = self.ctx.project_name();
+
+        match cmd {
+            LocalCommand::Ps { table } => {
+                let containers = prov
+                    .list_containers(project_name, None)
+                    .await?
+                    .into_iter()
+                    .map(|(c, _)| c)
+                    .collect::<Vec<_>>();
+                match self.output_mode {
+                    OutputMode::Normal => {
+                        print!("{}", get_local_containers_table(&containers, table.raw))
+                    }
+                    OutputMode::Json => {
+                        println!("{}", serde_json::to_string_pretty(&containers).unwrap())
+                    }
+                }
+            }
+            LocalCommand::Stop { resource } => {
+                prov.stop_containers(project_name, resource.as_deref())
+                    .await?
+            }
+            LocalCommand::Down {
+                resource,
+                volumes,
+                confirmation: ConfirmationArgs { yes },
+            } => {
+                if volumes && !yes {
+                    eprintln!(
+                        "{}",
+                        formatdoc!(
+                            "
+                        WARNING:
+                            Are you sure you want to remove the local resource containers and their volumes?
+                            All data in them will be lost."
+                        )
+                        .bold()
+                        .red()
+                    );
+                    if !Confirm::with_theme(&ColorfulTheme::default())
+                        .with_prompt("Are you sure?")
+                        .default(false)
+                        .interact()
+                        .unwrap()
+                    {
+                        return Ok(());
+                    }
+                }
+                prov.remove_containers(project_name, resource.as_deref(), volumes)
+                    .await?
+            }
+            LocalCommand::Logs {
+                resource,
+                follow,
+                tail,
+            } => {
+                prov.container_logs(project_name, &resource, follow, tail)
+                    .await?
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn resource_reset_local(
+        &self,
+        resource_type: ResourceType,
+        no_confirm: bool,
+    ) -> Result<()> {
+        if !no_confirm {
+            eprintln!(
+                "{}",
+                formatdoc!(
+                    "
+                WARNING:
+                    Are you sure you want to reset the local {}?
+                    All data in it will be lost.",
+                    resource_type
+                )
+                .bold()
+                .red()
+            );
+            if !Confirm::with_theme(&ColorfulTheme::default())
+                .with_prompt("Are you sure?")
+                .default(false)
+                .interact()
+                .unwrap()
+            {
+                return Ok(());
+            }
+        }
+
+        LocalProvisioner::new(self.ctx.local_config())?
+            .reset_database(self.ctx.project_name(), resource_type)
+            .await?;
+
+        eprintln!("The container and its volume will be created again on the next `shuttle run`.");
+
+        Ok(())
+    }
+
+    async fn resource_delete(&self, resource_type: &ResourceType, no_confirm: bool) -> Result<()> {
+        let client = self.client.as_ref().unwrap();
+
+        if !no_confirm {
+            eprintln!(
+                "{}",
+                formatdoc!(
+                    "
+                WARNING:
+                    Are you sure you want to delete this project's {}?
+                    This action is permanent.",
+                    resource_type
+                )
+                .bold()
+                .red()
+            );
+            if !Confirm::with_theme(&ColorfulTheme::default())
+                .with_prompt("Are you sure?")
+                .default(false)
+                .interact()
+                .unwrap()
+            {
+                return Ok(());
+            }
+        }
+
+        let msg = client
+            .delete_service_resource(self.ctx.project_id(), resource_type)
+            .await?
+            .into_inner();
+        println!("{msg}");
+
+        eprintln!(
+            "{}",
+            formatdoc! {"
+                Note:
+                    Remember to remove the resource annotation from your #[shuttle_runtime::main] function.
+                    Otherwise, it will be provisioned again during the next deployment."
+            }
+            .yellow(),
+        );
+
+        Ok(())
+    }
+
+    async fn resource_dump_local(
+        &self,
+        resource_type: ResourceType,
+        database: Option<String>,
+        output: Option<PathBuf>,
+    ) -> Result<()> {
+        let mut out: Box<dyn Write + Send> = match output.as_ref() {
+            Some(path) => Box::new(
+                fs::File::create(path).with_context(|| format!("creating {}", path.display()))?,
+            ),
+            None => Box::new(std::io::stdout()),
+        };
+
+        LocalProvisioner::new(self.ctx.local_config())?
+            .dump_database(self.ctx.project_name(), resource_type, database, &mut out)
+            .await?;
+
+        if let Some(path) = output {
+            eprintln!("Wrote dump to {}", path.display());
+        }
+
+        Ok(())
+    }
+
+    async fn resource_restore_local(
+        &self,
+        resource_type: ResourceType,
+        database: Option<String>,
+        input: PathBuf,
+        no_confirm: bool,
+    ) -> Result<()> {
+        let mut dump = tokio::fs::File::open(&input)
+            .await
+            .with_context(|| format!("opening {}", input.display()))?;
+
+        if !no_confirm {
+            eprintln!(
+                "{}",
+                formatdoc!(
+                    "
+                WARNING:
+                    Are you sure you want to load {} into the local {}?
+                    Existing data may be overwritten.",
+                    input.display(),
+                    resource_type
+                )
+                .bold()
+                .red()
+            );
+            if !Confirm::with_theme(&ColorfulTheme::default())
+                .with_prompt("Are you sure?")
+                .default(false)
+                .interact()
+                .unwrap()
+            {
+                return Ok(());
+            }
+        }
+
+        LocalProvisioner::new(self.ctx.local_config())?
+            .restore_database(
+                self.ctx.project_name(),
+                resource_type.clone(),
+                database,
+                &mut dump,
+            )
+            .await?;
+
+        eprintln!(
+            "Restored the local {resource_type} from {}",
+            input.display()
+        );
+
+        Ok(())
+    }
+
+    async fn list_certificates(&self, table_args: TableArgs) -> Result<()> {
+        let client = self.client.as_ref().unwrap();
+        let r = client.list_certificates(self.ctx.project_id()).await?;
+
+        match self.output_mode {
+            OutputMode::Normal => {
+                let table =
+                    get_certificates_table(r.into_inner().certificates.as_ref(), table_args.raw);
+                println!("{table}");
+            }
+            OutputMode::Json => {
+                println!("{}", r.raw_json);
+            }
+        }
+
+        Ok(())
+    }
+    async fn add_certificate(&self, domain: String) -> Result<()> {
+        let client = self.client.as_ref().unwrap();
+        let r = client
+            .add_certificate(self.ctx.project_id(), domain.clone())
+            .await?;
+
+        match self.output_mode {
+            OutputMode::Normal => {
+                println!("Added certificate for {}", r.into_inner().subject);
+            }
+            OutputMode::Json => {
+                println!("{}", r.raw_json);
+            }
+        }
+
+        Ok(())
+    }
+    async fn delete_certificate(&self, domain: String, no_confirm: bool) -> Result<()> {
+        let client = self.client.as_ref().unwrap();
+
+        if !no_confirm {
+            eprintln!(
+                "{}",
+                formatdoc!(
+                    "
                 WARNING:
                     Delete the certificate for {}?",
                     domain
@@ -1323,25 +1918,57 @@ impl Shuttle {
                 .find(|&secrets_file| secrets_file.exists() && secrets_file.is_file())
         });
 
-        let Some(secrets_file) = secrets_file else {
-            trace!("No secrets file was found");
-            return Ok(None);
+        let Some(profile) = args.profile.as_deref() else {
+            let Some(secrets_file) = secrets_file else {
+                trace!("No secrets file was found");
+                return Ok(None);
+            };
+            return Shuttle::load_secrets_file(secrets_file);
         };
 
+        let profile_file = workspace_root.join(format!("Secrets.{profile}.toml"));
+        if !profile_file.is_file() {
+            bail!(
+                "Secrets file for profile '{profile}' not found: {}",
+                profile_file.display()
+            );
+        }
+
+        let mut secrets = HashMap::new();
+        if args.layer {
+            if let Some(base) = secrets_file {
+                secrets.extend(Shuttle::load_secrets_file(base)?.unwrap_or_default());
+            }
+        }
+        trace!("Loading secrets for profile '{profile}'");
+        secrets.extend(Shuttle::read_secrets_file(&profile_file)?);
+
+        Ok(Some(secrets))
+    }
+
+    /// Load a secrets file, skipping it with a warning if it can't be read
+    fn load_secrets_file(secrets_file: &Path) -> Result<Option<HashMap<String, String>>> {
         trace!("Loading secrets from {}", secrets_file.display());
         let Ok(secrets_str) = fs::read_to_string(secrets_file) else {
             tracing::warn!("Failed to read secrets file, no secrets were loaded");
             return Ok(None);
         };
 
-        let secrets = toml::from_str::<HashMap<String, String>>(&secrets_str)
-            .context("parsing secrets file")?;
+        let secrets = parse_secrets_file(secrets_file, &secrets_str)?;
         trace!(keys = ?secrets.keys(), "Loaded secrets");
 
         Ok(Some(secrets))
     }
 
-    async fn pre_local_run(&self, run_args: &RunArgs) -> Result<BuiltService> {
+    /// Read and parse a secrets file
+    fn read_secrets_file(path: &Path) -> Result<HashMap<String, String>> {
+        let secrets_str = fs::read_to_string(path)
+            .with_context(|| format!("reading secrets file {}", path.display()))?;
+
+        parse_secrets_file(path, &secrets_str)
+    }
+
+    async fn pre_local_run(&self, run_args: &RunArgs) -> Result<Vec<BuiltService>> {
         trace!("starting a local run with args: {run_args:?}");
 
         let (tx, mut rx) = tokio::sync::mpsc::channel::<String>(256);
@@ -1359,7 +1986,33 @@ impl Shuttle {
             project_directory.display()
         );
 
-        build_workspace(project_directory, run_args.release, tx).await
+        let mut services = Vec::new();
+        for options in self.run_build_options(run_args).await? {
+            services.push(
+                build_workspace(project_directory, run_args.release, &options, tx.clone()).await?,
+            );
+        }
+
+        Ok(services)
+    }
+
+    /// Build options for each of the services to run
+    async fn run_build_options(&self, run_args: &RunArgs) -> Result<Vec<CargoBuildOptions>> {
+        let packages = if run_args.all {
+            let metadata =
+                async_cargo_metadata(&self.ctx.project_directory().join("Cargo.toml")).await?;
+            find_shuttle_packages(&metadata, None)?
+                .into_iter()
+                .map(|(package, _, _)| package.name)
+                .collect()
+        } else {
+            run_args.cargo_args.package.clone()
+        };
+        run_build_options(packages, &run_args.cargo_args, self.ctx.build_config())
+    }
+
+    fn cargo_build_options(&self, args: &CargoBuildArgs) -> Result<CargoBuildOptions> {
+        cargo_build_options(args, self.ctx.build_config())
     }
 
     fn find_available_port(run_args: &mut RunArgs) {
@@ -1386,65 +2039,248 @@ impl Shuttle {
 
         // Handle bacon mode
         if run_args.bacon {
-            println!(
+            self.print_banner(format!(
                 "\n    {} {} in watch mode using bacon\n",
                 "Starting".bold().green(),
                 project_name
-            );
+            ));
             return bacon::run_bacon(project_directory).await;
         }
 
-        let service = self.pre_local_run(&run_args).await?;
-        trace!(path = ?service.executable_path, "runtime executable");
+        let mut services = self.pre_local_run(&run_args).await?;
+        // Log lines are only prefixed with the package name when several services are running
+        let multiple = services.len() > 1;
+        for service in &services {
+            trace!(path = ?service.executable_path, "runtime executable");
+            if let Some(warning) = check_and_warn_runtime_version(&service.executable_path).await? {
+                eprint!("{}", warning);
+            }
+        }
 
         let secrets = Shuttle::get_secrets(&run_args.secret_args, project_directory, true)?
             .unwrap_or_default();
         Shuttle::find_available_port(&mut run_args);
-        if let Some(warning) = check_and_warn_runtime_version(&service.executable_path).await? {
-            eprint!("{}", warning);
-        }
 
-        let runtime_executable = service.executable_path.clone();
         let api_port = portpicker::pick_unused_port()
             .expect("failed to find available port for local provisioner server");
         let api_addr = SocketAddr::new(Ipv4Addr::LOCALHOST.into(), api_port);
-        let healthz_port = portpicker::pick_unused_port()
-            .expect("failed to find available port for runtime health check");
         let ip = if run_args.external {
             Ipv4Addr::UNSPECIFIED
         } else {
             Ipv4Addr::LOCALHOST
         };
 
+        // The provisioner server (and the containers it starts) outlive runtime restarts in watch mode,
+        // and are shared by all services in the run
         let state = Arc::new(ProvApiState {
             project_name: project_name.clone(),
             secrets,
+            local: self.ctx.local_config(),
+            project_directory: project_directory.to_path_buf(),
+            provision_lock: Default::default(),
         });
         tokio::spawn(async move { ProvisionerServer::run(state, &api_addr).await });
 
-        println!(
-            "\n    {} {} on http://{}:{}\n",
-            "Starting".bold().green(),
-            service.target_name,
-            ip,
-            run_args.port,
-        );
+        // Each service gets its own port, counting up from the requested one
+        let mut ports = vec![run_args.port];
+        for _ in 1..services.len() {
+            let previous = *ports.last().unwrap();
+            let port = (previous + 1..=u16::MAX)
+                .find(|p| portpicker::is_free_tcp(*p))
+                .context("failed to find available port for service")?;
+            ports.push(port);
+        }
 
-        let mut envs = vec![
-            ("SHUTTLE_BETA", "true".to_owned()),
-            ("SHUTTLE_PROJECT_ID", "proj_LOCAL".to_owned()),
-            ("SHUTTLE_PROJECT_NAME", project_name),
-            ("SHUTTLE_ENV", Environment::Local.to_string()),
-            ("SHUTTLE_RUNTIME_IP", ip.to_string()),
-            ("SHUTTLE_RUNTIME_PORT", run_args.port.to_string()),
-            ("SHUTTLE_HEALTHZ_PORT", healthz_port.to_string()),
-            ("SHUTTLE_API", format!("http://127.0.0.1:{}", api_port)),
-        ];
-        // Use a nice debugging tracing level if user does not provide their own
-        if debug && std::env::var("RUST_LOG").is_err() {
-            envs.push(("RUST_LOG", "info,shuttle=trace,reqwest=debug".to_owned()));
+        let mut envs = Vec::new();
+        for port in &ports {
+            let healthz_port = portpicker::pick_unused_port()
+                .expect("failed to find available port for runtime health check");
+            let mut service_envs = vec![
+                ("SHUTTLE_BETA", "true".to_owned()),
+                ("SHUTTLE_PROJECT_ID", "proj_LOCAL".to_owned()),
+                ("SHUTTLE_PROJECT_NAME", project_name.clone()),
+                ("SHUTTLE_ENV", Environment::Local.to_string()),
+                ("SHUTTLE_RUNTIME_IP", ip.to_string()),
+                ("SHUTTLE_RUNTIME_PORT", port.to_string()),
+                ("SHUTTLE_HEALTHZ_PORT", healthz_port.to_string()),
+                ("SHUTTLE_API", format!("http://127.0.0.1:{}", api_port)),
+            ];
+            // Use a nice debugging tracing level if user does not provide their own
+            if debug && std::env::var("RUST_LOG").is_err() {
+                service_envs.push(("RUST_LOG", "info,shuttle=trace,reqwest=debug".to_owned()));
+            }
+            envs.push(service_envs);
+
+            // Start background task for simulated health check
+            tokio::spawn(async move {
+                loop {
+                    // ECS health check runs ever 5s
+                    tokio::time::sleep(tokio::time::Duration::from_millis(5000)).await;
+
+                    tracing::trace!("Health check against runtime");
+                    if let Err(e) =
+                        reqwest::get(format!("http://127.0.0.1:{}/", healthz_port)).await
+                    {
+                        tracing::trace!("Health check against runtime failed: {e}");
+                    }
+                }
+            });
+        }
+
+        let mut changes = if run_args.watch {
+            Some(watch_files(
+                project_directory.to_owned(),
+                self.ctx.include().cloned(),
+            )?)
+        } else {
+            None
+        };
+
+        let mut runtimes = Vec::new();
+        for ((service, envs), port) in services.iter().zip(&envs).zip(&ports) {
+            self.print_banner(format!(
+                "\n    {} {} on http://{}:{}\n",
+                "Starting".bold().green(),
+                service.target_name,
+                ip,
+                port,
+            ));
+            runtimes.push(Some(Shuttle::spawn_runtime(
+                service,
+                envs,
+                run_args.raw,
+                self.log_format,
+                &run_args.log_filter,
+                multiple.then_some(service.package_name.as_str()),
+            )?));
+        }
+
+        // Created once, so that a signal that arrives while rebuilding is not missed
+        let shutdown = wait_for_shutdown_signal();
+        tokio::pin!(shutdown);
+
+        loop {
+            tokio::select! {
+                (index, exit_result) = wait_for_any_runtime(&mut runtimes) => {
+                    runtimes[index] = None;
+                    let exit_status = match exit_result {
+                        Ok(exit_status) => exit_status,
+                        Err(e) => bail!("Failed to wait for runtime process to exit: {e}"),
+                    };
+                    let process = if multiple {
+                        format!("Runtime process for {}", services[index].package_name)
+                    } else {
+                        "Runtime process".to_owned()
+                    };
+                    if !run_args.watch {
+                        bail!(
+                            "{process} exited with code {}",
+                            exit_status.code().unwrap_or_default()
+                        );
+                    }
+                    eprintln!(
+                        "{}",
+                        format!(
+                            "{process} exited with code {}. Waiting for changes...",
+                            exit_status.code().unwrap_or_default()
+                        )
+                        .yellow()
+                    );
+                }
+                Some(mut changed) = next_change(&mut changes) => {
+                    // collect changes that happened while the previous ones were queued
+                    if let Some(rx) = changes.as_mut() {
+                        while let Ok(more) = rx.try_recv() {
+                            changed.extend(more);
+                        }
+                    }
+                    trace!(?changed, "restarting due to file changes");
+                    for runtime in runtimes.iter_mut() {
+                        if let Some(mut r) = runtime.take() {
+                            r.kill().await?;
+                        }
+                    }
+                    self.print_banner(format!(
+                        "\n    {} {} file(s) changed, rebuilding\n",
+                        "Detected".bold().green(),
+                        changed.len(),
+                    ));
+                    let rebuilt = tokio::select! {
+                        rebuilt = self.pre_local_run(&run_args) => rebuilt,
+                        _ = &mut shutdown => return Ok(()),
+                    };
+                    match rebuilt {
+                        Ok(s) => services = s,
+                        Err(e) => {
+                            eprintln!("{}", format!("{e:#}. Waiting for changes...").red());
+                            continue;
+                        }
+                    }
+                    for (index, ((service, envs), port)) in
+                        services.iter().zip(&envs).zip(&ports).enumerate()
+                    {
+                        self.print_banner(format!(
+                            "\n    {} {} on http://{}:{}\n",
+                            "Restarting".bold().green(),
+                            service.target_name,
+                            ip,
+                            port,
+                        ));
+                        runtimes[index] = Some(Shuttle::spawn_runtime(
+                            service,
+                            envs,
+                            run_args.raw,
+                            self.log_format,
+                            &run_args.log_filter,
+                            multiple.then_some(service.package_name.as_str()),
+                        )?);
+                    }
+                }
+                _ = &mut shutdown => {
+                    for runtime in runtimes.iter_mut() {
+                        if let Some(mut r) = runtime.take() {
+                            r.kill().await?;
+                        }
+                    }
+                    return Ok(());
+                }
+            }
+        }
+    }
+
+    /// Print a deployment that was just created, for when its status is not followed
+    fn print_deployment(&self, deployment: &DeploymentResponse, raw_json: &str) {
+        match self.output_mode {
+            OutputMode::Normal => {
+                println!("{}", deployment.to_string_colored());
+            }
+            OutputMode::Json => {
+                println!("{}", raw_json);
+            }
         }
+    }
+
+    /// Print a status banner of a local run. These go to stderr when logs are printed in a structured
+    /// format, so that the log stream on stdout can be parsed.
+    fn print_banner(&self, banner: String) {
+        if self.log_format == LogFormat::Text {
+            println!("{banner}");
+        } else {
+            eprintln!("{banner}");
+        }
+    }
 
+    /// Spawns the runtime process and starts background tasks that forward its stdout and stderr.
+    /// If a prefix is given, it is used as the log source and prepended to raw log lines.
+    fn spawn_runtime(
+        service: &BuiltService,
+        envs: &[(&str, String)],
+        raw: bool,
+        format: LogFormat,
+        filter: &LogFilterArgs,
+        prefix: Option<&str>,
+    ) -> Result<tokio::process::Child> {
+        let runtime_executable = service.executable_path.clone();
         info!(
             path = %runtime_executable.display(),
             "Spawning runtime process",
@@ -1453,7 +2289,7 @@ impl Shuttle {
             dunce::canonicalize(runtime_executable).context("canonicalize path of executable")?,
         )
         .current_dir(&service.workspace_path)
-        .envs(envs)
+        .envs(envs.iter().map(|(k, v)| (k, v)))
         .stdout(std::process::Stdio::piped())
         .stderr(std::process::Stdio::piped())
         .kill_on_drop(true)
@@ -1461,7 +2297,6 @@ impl Shuttle {
         .context("spawning runtime process")?;
 
         // Start background tasks for reading runtime's stdout and stderr
-        let raw = run_args.raw;
         let mut stdout_reader = BufReader::new(
             runtime
                 .stdout
@@ -1469,14 +2304,25 @@ impl Shuttle {
                 .context("child process did not have a handle to stdout")?,
         )
         .lines();
-        tokio::spawn(async move {
-            while let Some(line) = stdout_reader.next_line().await.unwrap() {
-                if raw {
-                    println!("{}", line);
-                } else {
-                    let log_item = LogItem::new(Utc::now(), "app".to_owned(), line);
-                    println!("{log_item}");
+        let prefix = prefix.map(ToOwned::to_owned);
+        let source = prefix.clone().unwrap_or_else(|| "app".to_owned());
+        let print_line = move |filter: &LogFilterArgs, line: String| {
+            let log_item = LogItem::new(Utc::now(), source.clone(), line);
+            if !filter.matches(&log_item) {
+                return;
+            }
+            match prefix {
+                Some(ref prefix) if raw && format == LogFormat::Text => {
+                    println!("[{prefix}] {}", log_item.line)
                 }
+                _ => println!("{}", format.format(&log_item, raw)),
+            }
+        };
+        let stdout_filter = filter.clone();
+        let stdout_print = print_line.clone();
+        tokio::spawn(async move {
+            while let Ok(Some(line)) = stdout_reader.next_line().await {
+                stdout_print(&stdout_filter, line);
             }
         });
         let mut stderr_reader = BufReader::new(
@@ -1486,106 +2332,82 @@ impl Shuttle {
                 .context("child process did not have a handle to stderr")?,
         )
         .lines();
+        let stderr_filter = filter.clone();
         tokio::spawn(async move {
-            while let Some(line) = stderr_reader.next_line().await.unwrap() {
-                if raw {
-                    println!("{}", line);
-                } else {
-                    let log_item = LogItem::new(Utc::now(), "app".to_owned(), line);
-                    println!("{log_item}");
-                }
+            while let Ok(Some(line)) = stderr_reader.next_line().await {
+                print_line(&stderr_filter, line);
             }
         });
 
-        // Start background task for simulated health check
-        tokio::spawn(async move {
-            loop {
-                // ECS health check runs ever 5s
-                tokio::time::sleep(tokio::time::Duration::from_millis(5000)).await;
+        Ok(runtime)
+    }
 
-                tracing::trace!("Health check against runtime");
-                if let Err(e) = reqwest::get(format!("http://127.0.0.1:{}/", healthz_port)).await {
-                    tracing::trace!("Health check against runtime failed: {e}");
-                }
-            }
-        });
+    /// Find the Rust build arguments and infra annotations for the Shuttle package selected by `options`
+    async fn rust_build_args(
+        manifest_path: &Path,
+        options: &CargoBuildOptions,
+    ) -> Result<(BuildArgsRust, Option<InfraRequest>)> {
+        let mut rust_build_args = BuildArgsRust::default();
 
-        #[cfg(target_family = "unix")]
-        let exit_result = {
-            let mut sigterm_notif =
-                tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())
-                    .expect("Can not get the SIGTERM signal receptor");
-            let mut sigint_notif =
-                tokio::signal::unix::signal(tokio::signal::unix::SignalKind::interrupt())
-                    .expect("Can not get the SIGINT signal receptor");
-            tokio::select! {
-                exit_result = runtime.wait() => {
-                    Some(exit_result)
-                }
-                _ = sigterm_notif.recv() => {
-                    eprintln!("Received SIGTERM. Killing the runtime...");
-                    None
-                },
-                _ = sigint_notif.recv() => {
-                    eprintln!("Received SIGINT. Killing the runtime...");
-                    None
-                }
+        let metadata = async_cargo_metadata(manifest_path).await?;
+        let (package, target, runtime_version) = find_shuttle_package(&metadata, options)?;
+        rust_build_args.package_name = Some(package.name.clone());
+        rust_build_args.binary_name = Some(target.name.clone());
+        rust_build_args.shuttle_runtime_version = runtime_version;
+
+        let (no_default_features, features) = options.cargo_features(&package);
+        rust_build_args.no_default_features = no_default_features;
+        rust_build_args.features = features;
+
+        let infra = parse_infra_from_code(
+            &fs::read_to_string(target.src_path.as_path())
+                .context("reading target file when extracting infra annotations")?,
+        )
+        .context("parsing infra annotations")?;
+
+        Ok((rust_build_args, infra))
+    }
+
+    /// Credentials for pulling `image`, from the arguments or the Docker config file
+    fn registry_credentials(
+        image: &str,
+        username: Option<String>,
+        password_stdin: bool,
+    ) -> Result<Option<RegistryCredentials>> {
+        let Some(username) = username else {
+            let credentials = docker_config_credentials(image)
+                .context("looking up registry credentials in the Docker config")?;
+            if credentials.is_some() {
+                eprintln!(
+                    "Using credentials for {} from the Docker config",
+                    registry_of(image)
+                );
             }
+            return Ok(credentials.map(|(username, password)| RegistryCredentials {
+                username,
+                password: Secret::new(password),
+            }));
         };
-        #[cfg(target_family = "windows")]
-        let exit_result = {
-            let mut ctrl_break_notif = tokio::signal::windows::ctrl_break()
-                .expect("Can not get the CtrlBreak signal receptor");
-            let mut ctrl_c_notif =
-                tokio::signal::windows::ctrl_c().expect("Can not get the CtrlC signal receptor");
-            let mut ctrl_close_notif = tokio::signal::windows::ctrl_close()
-                .expect("Can not get the CtrlClose signal receptor");
-            let mut ctrl_logoff_notif = tokio::signal::windows::ctrl_logoff()
-                .expect("Can not get the CtrlLogoff signal receptor");
-            let mut ctrl_shutdown_notif = tokio::signal::windows::ctrl_shutdown()
-                .expect("Can not get the CtrlShutdown signal receptor");
-            tokio::select! {
-                exit_result = runtime.wait() => {
-                    Some(exit_result)
-                }
-                _ = ctrl_break_notif.recv() => {
-                    eprintln!("Received ctrl-break.");
-                    None
-                },
-                _ = ctrl_c_notif.recv() => {
-                    eprintln!("Received ctrl-c.");
-                    None
-                },
-                _ = ctrl_close_notif.recv() => {
-                    eprintln!("Received ctrl-close.");
-                    None
-                },
-                _ = ctrl_logoff_notif.recv() => {
-                    eprintln!("Received ctrl-logoff.");
-                    None
-                },
-                _ = ctrl_shutdown_notif.recv() => {
-                    eprintln!("Received ctrl-shutdown.");
-                    None
-                }
-            }
+
+        let password = if password_stdin {
+            let mut password = String::new();
+            std::io::stdin()
+                .read_to_string(&mut password)
+                .context("reading registry password from stdin")?;
+            password.trim_end_matches(['\r', '\n']).to_owned()
+        } else {
+            Password::with_theme(&ColorfulTheme::default())
+                .with_prompt(format!("Password for {username}@{}", registry_of(image)))
+                .interact()?
         };
-        match exit_result {
-            Some(Ok(exit_status)) => {
-                bail!(
-                    "Runtime process exited with code {}",
-                    exit_status.code().unwrap_or_default()
-                );
-            }
-            Some(Err(e)) => {
-                bail!("Failed to wait for runtime process to exit: {e}");
-            }
-            None => {
-                runtime.kill().await?;
-            }
+        if password.is_empty() {
+            bail!("Registry password can not be empty");
         }
 
-        Ok(())
+        Ok(Some(RegistryCredentials {
+            username,
+            password: Secret::new(password),
+        }))
     }
 
     async fn deploy(&mut self, args: DeployArgs) -> Result<()> {
@@ -1594,34 +2416,109 @@ impl Shuttle {
         let manifest_path = project_directory.join("Cargo.toml");
 
         let secrets = Shuttle::get_secrets(&args.secret_args, project_directory, false)?;
+        let cargo_options = self.cargo_build_options(&args.cargo_args)?;
 
         // Image deployment mode
         if let Some(image) = args.image {
             let pid = self.ctx.project_id();
-            let deployment_req_image = DeploymentRequestImage { image, secrets };
+            let credentials = Shuttle::registry_credentials(
+                &image,
+                args.image_username,
+                args.image_password_stdin,
+            )?;
+            let deployment_req_image = DeploymentRequestImage {
+                image,
+                secrets,
+                credentials,
+                build_meta: Some(BuildMeta {
+                    secrets_profile: args.secret_args.profile,
+                    ..Default::default()
+                }),
+            };
 
             let (deployment, raw_json) = client
-                .deploy(pid, DeploymentRequest::Image(deployment_req_image))
+                .deploy(
+                    pid,
+                    DeploymentRequest::Image(Box::new(deployment_req_image)),
+                )
                 .await?
                 .into_parts();
 
             if args.tracking_args.no_follow {
-                match self.output_mode {
-                    OutputMode::Normal => {
-                        println!("{}", deployment.to_string_colored());
-                    }
-                    OutputMode::Json => {
-                        println!("{}", raw_json);
-                    }
-                }
+                self.print_deployment(&deployment, &raw_json);
+                return Ok(());
+            }
+
+            return self
+                .track_new_deployment(
+                    pid,
+                    &deployment.id,
+                    args.tracking_args.raw,
+                    args.rollback_on_failure,
+                )
+                .await;
+        }
+
+        // Git repository deployment mode
+        if let Some(url) = args.git {
+            let rev = args.rev.expect("--rev to be required by --git");
+
+            eprintln!("Resolving {rev} in {url}...");
+            let resolved = {
+                let url = url.clone();
+                tokio::task::spawn_blocking(move || crate::init::gix_resolve_rev(&url, &rev))
+                    .await??
+            };
+            trace!(?resolved, "resolved git revision");
+
+            // The repository is not checked out, so the builder finds the package, binary and infra
+            // annotations, and only the build options that were given explicitly are passed on
+            let rust_build_args = BuildArgsRust {
+                package_name: cargo_options.package.clone(),
+                binary_name: cargo_options.bin.clone(),
+                features: (!cargo_options.features.is_empty())
+                    .then(|| cargo_options.features.join(",")),
+                no_default_features: cargo_options.no_default_features.unwrap_or_default(),
+                ..Default::default()
+            };
+
+            let deployment_req = DeploymentRequestGitRepo {
+                url,
+                commit: resolved.commit.clone(),
+                subfolder: args.subfolder,
+                build_args: Some(BuildArgs::Rust(rust_build_args)),
+                secrets,
+                build_meta: Some(BuildMeta {
+                    git_commit_id: Some(resolved.commit),
+                    // Left out, since only the refs are listed and the commit itself is never fetched
+                    git_commit_msg: None,
+                    git_branch: resolved
+                        .branch
+                        .map(|b| b.chars().take(GIT_STRINGS_MAX_LENGTH).collect()),
+                    git_dirty: Some(false),
+                    secrets_profile: args.secret_args.profile,
+                }),
+                infra: None,
+            };
+
+            let pid = self.ctx.project_id();
+            eprintln!("Creating deployment...");
+            let (deployment, raw_json) = client
+                .deploy(pid, DeploymentRequest::GitRepo(Box::new(deployment_req)))
+                .await?
+                .into_parts();
+
+            if args.tracking_args.no_follow {
+                self.print_deployment(&deployment, &raw_json);
                 return Ok(());
             }
 
             return self
-                .track_deployment_status_and_print_logs_on_fail(
+                .track_new_deployment(
                     pid,
                     &deployment.id,
                     args.tracking_args.raw,
+                    args.rollback_on_failure,
                 )
                 .await;
         }
@@ -1631,36 +2528,15 @@ impl Shuttle {
             secrets,
             ..Default::default()
         };
-        let mut build_meta = BuildMeta::default();
-        let mut rust_build_args = BuildArgsRust::default();
-
-        let metadata = async_cargo_metadata(manifest_path.as_path()).await?;
-        // TODO: support overriding this
-        let (package, target, runtime_version) = find_first_shuttle_package(&metadata)?;
-        rust_build_args.package_name = Some(package.name.clone());
-        rust_build_args.binary_name = Some(target.name.clone());
-        rust_build_args.shuttle_runtime_version = runtime_version;
-
-        // activate shuttle feature if present
-        let (no_default_features, features) = if package.features.contains_key("shuttle") {
-            (true, Some(vec!["shuttle".to_owned()]))
-        } else {
-            (false, None)
+        let mut build_meta = BuildMeta {
+            secrets_profile: args.secret_args.profile.clone(),
+            ..Default::default()
         };
-        rust_build_args.no_default_features = no_default_features;
-        rust_build_args.features = features.map(|v| v.join(","));
-
-        // TODO: determine which (one) binary to build
 
+        let (rust_build_args, infra) =
+            Shuttle::rust_build_args(manifest_path.as_path(), &cargo_options).await?;
         deployment_req.build_args = Some(BuildArgs::Rust(rust_build_args));
-
-        // TODO: have all of the above be configurable in CLI and Shuttle.toml
-
-        deployment_req.infra = parse_infra_from_code(
-            &fs::read_to_string(target.src_path.as_path())
-                .context("reading target file when extracting infra annotations")?,
-        )
-        .context("parsing infra annotations")?;
+        deployment_req.infra = infra;
 
         if let Ok(repo) = Repository::discover(project_directory) {
             let repo_path = repo
@@ -1693,10 +2569,9 @@ impl Shuttle {
             }
         }
 
-        eprintln!("Packing files...");
-        let archive = self.make_archive()?;
-
         if let Some(path) = args.output_archive {
+            eprintln!("Packing files...");
+            let archive = self.make_archive(&self.gather_build_files()?)?;
             eprintln!("Writing archive to {}", path.display());
             std::fs::write(path, archive).context("writing archive")?;
 
@@ -1707,9 +2582,35 @@ impl Shuttle {
 
         let pid = self.ctx.project_id();
 
-        eprintln!("Uploading code...");
-        let arch = client.upload_archive(pid, archive).await?.into_inner();
-        deployment_req.archive_version_id = arch.archive_version_id;
+        let incremental = if self.ctx.incremental_upload().is_some_and(|i| i) {
+            self.upload_archive_incremental(pid)
+                .await
+                .inspect_err(|e| {
+                    warn!(error = ?e, "incremental upload failed");
+                    eprintln!(
+                        "{}",
+                        "Incremental upload failed. Uploading the full archive instead.".yellow()
+                    );
+                })
+                .ok()
+        } else {
+            None
+        };
+        deployment_req.archive_version_id = match incremental {
+            Some(archive_version_id) => archive_version_id,
+            None => {
+                eprintln!("Packing files...");
+                let archive_files = self.gather_build_files()?;
+                let archive = self.make_archive(&archive_files)?;
+                self.check_archive_size(&archive_files, archive.len() as u64, true)?;
+                eprintln!("Uploading code...");
+                client
+                    .upload_archive(pid, archive)
+                    .await?
+                    .into_inner()
+                    .archive_version_id
+            }
+        };
         deployment_req.build_meta = Some(build_meta);
 
         eprintln!("Creating deployment...");
@@ -1721,24 +2622,103 @@ impl Shuttle {
             .await?
             .into_parts();
 
-        if args.tracking_args.no_follow {
-            match self.output_mode {
-                OutputMode::Normal => {
-                    println!("{}", deployment.to_string_colored());
-                }
-                OutputMode::Json => {
-                    println!("{}", raw_json);
+        if args.tracking_args.no_follow {
+            self.print_deployment(&deployment, &raw_json);
+            return Ok(());
+        }
+
+        self.track_new_deployment(
+            pid,
+            &deployment.id,
+            args.tracking_args.raw,
+            args.rollback_on_failure,
+        )
+        .await
+    }
+
+    /// Upload only the file contents that the platform has not seen before,
+    /// then have the archive assembled from them.
+    /// Returns the archive version id.
+    async fn upload_archive_incremental(&self, pid: &str) -> Result<String> {
+        let client = self.client.as_ref().unwrap();
+
+        let archive_files = self.gather_build_files()?;
+        if archive_files.is_empty() {
+            bail!("No files included in upload.");
+        }
+
+        // The archive is assembled remotely, so the uncompressed size is the best estimate here
+        let total_size = archive_files
+            .keys()
+            .map(|path| path.metadata().map(|m| m.len()).unwrap_or_default())
+            .sum();
+        self.check_archive_size(&archive_files, total_size, false)?;
+
+        eprintln!("Hashing files...");
+        let (manifest, blobs) = build_manifest(&archive_files)?;
+
+        let mut cache = BlobCache::load(pid)?;
+        let unknown = blobs
+            .keys()
+            .filter(|hash| !cache.contains(hash))
+            .cloned()
+            .collect::<Vec<_>>();
+        let missing = if unknown.is_empty() {
+            Vec::new()
+        } else {
+            client
+                .check_archive_blobs(pid, unknown)
+                .await?
+                .into_inner()
+                .missing
+        };
+
+        eprintln!(
+            "Uploading code ({} of {} files changed)...",
+            missing.len(),
+            blobs.len()
+        );
+        for hash in missing {
+            let path = blobs
+                .get(&hash)
+                .with_context(|| format!("API requested unknown blob {hash}"))?;
+            debug!("Uploading {path:?}");
+            let bytes = fs::read(path).with_context(|| format!("reading {}", path.display()))?;
+            client.upload_archive_blob(pid, &hash, bytes).await?;
+        }
+        cache.extend(blobs.into_keys());
+        cache.save()?;
+
+        match client.upload_archive_manifest(pid, manifest).await {
+            Ok(r) => Ok(r.into_inner().archive_version_id),
+            Err(e) => {
+                // the cache might be out of sync with what the platform has stored
+                cache.clear();
+                cache.save()?;
+                Err(e)
+            }
+        }
+    }
+
+    fn deploy_dry_run(&self) -> Result<()> {
+        let files = gather_build_files(self.ctx.project_directory(), self.ctx.include())?;
+        let archive = self.make_archive(&files.included)?;
+        let report = ArchiveReport::new(&files, archive.len() as u64)?;
+
+        match self.output_mode {
+            OutputMode::Normal => {
+                println!("{}", report.to_string_colored());
+                if let Err(e) = self.check_archive_size(&files.included, report.archive_size, true)
+                {
+                    eprintln!("{}", format!("{e}").yellow());
                 }
             }
-            return Ok(());
+            OutputMode::Json => {
+                println!("{}", serde_json::to_string(&report).unwrap());
+            }
         }
 
-        self.track_deployment_status_and_print_logs_on_fail(
-            pid,
-            &deployment.id,
-            args.tracking_args.raw,
-        )
-        .await
+        Ok(())
     }
 
     /// Returns true if the deployment failed
@@ -1792,26 +2772,65 @@ impl Shuttle {
         depl_id: &str,
         raw: bool,
     ) -> Result<()> {
-        let client = self.client.as_ref().unwrap();
         let failed = self.track_deployment_status(proj_id, depl_id).await?;
         if failed {
-            let r = client.get_deployment_logs(proj_id, depl_id).await?;
-            match self.output_mode {
-                OutputMode::Normal => {
-                    let logs = r.into_inner().logs;
-                    for log in logs {
-                        if raw {
-                            println!("{}", log.line);
-                        } else {
-                            println!("{log}");
-                        }
-                    }
-                }
-                OutputMode::Json => {
-                    println!("{}", r.raw_json);
+            self.print_deployment_logs(proj_id, depl_id, raw).await?;
+            return Err(anyhow!("Deployment failed"));
+        }
+
+        Ok(())
+    }
+
+    /// Track a new deployment, and optionally roll back to the last healthy deployment if it fails
+    async fn track_new_deployment(
+        &self,
+        proj_id: &str,
+        depl_id: &str,
+        raw: bool,
+        rollback_on_failure: bool,
+    ) -> Result<()> {
+        if !rollback_on_failure {
+            return self
+                .track_deployment_status_and_print_logs_on_fail(proj_id, depl_id, raw)
+                .await;
+        }
+
+        let failed = self.track_deployment_status(proj_id, depl_id).await?;
+        if !failed {
+            return Ok(());
+        }
+        self.print_deployment_logs(proj_id, depl_id, raw).await?;
+
+        eprintln!(
+            "{}",
+            "Deployment failed. Rolling back to the last healthy deployment...".yellow()
+        );
+        self.rollback_from(
+            proj_id,
+            depl_id,
+            &DeploymentTrackingArgs {
+                no_follow: false,
+                raw,
+            },
+        )
+        .await?;
+
+        Err(anyhow!("Deployment failed"))
+    }
+
+    async fn print_deployment_logs(&self, proj_id: &str, depl_id: &str, raw: bool) -> Result<()> {
+        let client = self.client.as_ref().unwrap();
+        let r = client.get_deployment_logs(proj_id, depl_id).await?;
+        match self.output_mode {
+            OutputMode::Normal => {
+                let logs = r.into_inner().logs;
+                for log in logs {
+                    self.print_log_item(&log, raw);
                 }
             }
-            return Err(anyhow!("Deployment failed"));
+            OutputMode::Json => {
+                println!("{}", r.raw_json);
+            }
         }
 
         Ok(())
@@ -1953,98 +2972,107 @@ impl Shuttle {
         Ok(())
     }
 
-    /// Find list of all files to include in a build, ready for placing in a zip archive
-    fn gather_build_files(&self) -> Result<BTreeMap<PathBuf, PathBuf>> {
-        let include_patterns = self.ctx.include();
-
-        let project_directory = self.ctx.project_directory();
-
-        //
-        // Mixing include and exclude overrides messes up the .ignore and .gitignore etc,
-        // therefore these "ignore" walk and the "include" walk are separate.
-        //
-        let mut entries = Vec::new();
-
-        // Default excludes
-        let ignore_overrides = OverrideBuilder::new(project_directory)
-            .add("!.git/")
-            .context("adding override `!.git/`")?
-            .add("!target/")
-            .context("adding override `!target/`")?
-            .build()
-            .context("building archive override rules")?;
-        for r in WalkBuilder::new(project_directory)
-            .hidden(false)
-            .overrides(ignore_overrides)
-            .build()
-        {
-            entries.push(r.context("list dir entry")?.into_path())
+    /// Build a Docker image of the project with the local container engine
+    async fn build_image(&self, args: ImageBuildArgs) -> Result<()> {
+        let manifest_path = self.ctx.project_directory().join("Cargo.toml");
+        let cargo_options = self.cargo_build_options(&args.cargo_args)?;
+        let (mut rust_build_args, _infra) =
+            Shuttle::rust_build_args(&manifest_path, &cargo_options).await?;
+        rust_build_args.cargo_chef = !args.no_cargo_chef;
+        rust_build_args.mold = args.mold;
+
+        let dockerfile = image::dockerfile(&rust_build_args)?;
+        if args.print_dockerfile {
+            print!("{dockerfile}");
+            return Ok(());
         }
 
-        // User provided includes
-        let mut globs = GlobSetBuilder::new();
-        if let Some(rules) = include_patterns {
-            for r in rules {
-                globs.add(Glob::new(r.as_str()).context(format!("parsing glob pattern {:?}", r))?);
-            }
-        }
+        let tag = match args.tag {
+            Some(tag) => tag,
+            None => format!(
+                "{}:latest",
+                rust_build_args
+                    .package_name
+                    .as_deref()
+                    .expect("package name to be set")
+                    .to_lowercase()
+            ),
+        };
 
-        // Find the files
-        let globs = globs.build().context("glob glob")?;
-        for entry in walkdir::WalkDir::new(project_directory) {
-            let path = entry.context("list dir")?.into_path();
-            if globs.is_match(
-                path.strip_prefix(project_directory)
-                    .context("strip prefix of path")?,
-            ) {
-                entries.push(path);
-            }
+        let archive_files = self.gather_build_files()?;
+        if archive_files.is_empty() {
+            bail!("No files included in build.");
         }
+        eprintln!("Packing files...");
+        let context = image::build_context(archive_files, &dockerfile)?;
 
-        let mut archive_files = BTreeMap::new();
-        for path in entries {
-            // It's not possible to add a directory to an archive
-            if path.is_dir() {
-                trace!("Skipping {:?}: is a directory", path);
-                continue;
+        eprintln!("Building image {tag}...");
+        let docker = connect_docker(self.ctx.local_config().docker_host.as_deref())?;
+        image::build_image(&docker, context, &tag).await?;
+
+        match self.output_mode {
+            OutputMode::Normal => {
+                eprintln!("{}", format!("Built image {tag}").green());
+                eprintln!(
+                    "Push it to a registry the platform can pull from, then deploy it with `shuttle deploy --image <image>`"
+                );
             }
-            // symlinks == chaos
-            if path.is_symlink() {
-                trace!("Skipping {:?}: is a symlink", path);
-                continue;
+            OutputMode::Json => {
+                println!("{}", serde_json::json!({ "image": tag }));
             }
-
-            // zip file puts all files in root
-            let name = path
-                .strip_prefix(project_directory)
-                .context("strip prefix of path")?
-                .to_owned();
-
-            archive_files.insert(path, name);
         }
 
-        Ok(archive_files)
+        Ok(())
+    }
+
+    /// Find list of all files to include in a build, ready for placing in a zip archive
+    fn gather_build_files(&self) -> Result<BTreeMap<PathBuf, PathBuf>> {
+        Ok(gather_build_files(self.ctx.project_directory(), self.ctx.include())?.included)
     }
 
-    fn make_archive(&self) -> Result<Vec<u8>> {
-        let archive_files = self.gather_build_files()?;
+    /// Zip the files from [`Shuttle::gather_build_files`]
+    fn make_archive(&self, archive_files: &BTreeMap<PathBuf, PathBuf>) -> Result<Vec<u8>> {
         if archive_files.is_empty() {
             error!("No files included in upload. Aborting...");
             bail!("No files included in upload.");
         }
 
+        let deploy_config = self.ctx.deploy_config();
+        let compression = deploy_config
+            .and_then(|d| d.compression)
+            .unwrap_or_default();
+        let options = FileOptions::<()>::default()
+            .compression_method(match compression {
+                ArchiveCompression::Stored => CompressionMethod::Stored,
+                ArchiveCompression::Deflate => CompressionMethod::Deflated,
+                ArchiveCompression::Zstd => CompressionMethod::Zstd,
+            })
+            .compression_level(deploy_config.and_then(|d| d.compression_level));
+        let large_file_warning = deploy_config
+            .and_then(|d| d.large_file_warning)
+            .unwrap_or(DEFAULT_LARGE_FILE_WARNING);
+
         let bytes = {
-            debug!("making zip archive");
+            debug!(?compression, "making zip archive");
             let mut zip = zip::ZipWriter::new(std::io::Cursor::new(Vec::new()));
             for (path, name) in archive_files {
                 debug!("Packing {path:?}");
 
-                // windows things
-                let name = name.to_str().expect("valid filename").replace('\\', "/");
-                zip.start_file(name, FileOptions::<()>::default())?;
+                zip.start_file(archive_path(name), options)?;
 
                 let mut b = Vec::new();
                 fs::File::open(path)?.read_to_end(&mut b)?;
+                if b.len() as u64 > large_file_warning {
+                    eprintln!(
+                        "{}",
+                        format!(
+                            "WARNING: Large file in archive: {} ({})",
+                            name.display(),
+                            format_size(b.len() as u64)
+                        )
+                        .yellow()
+                    );
+                }
                 zip.write_all(&b)?;
             }
             let r = zip.finish().context("finish encoding zip archive")?;
@@ -2055,6 +3083,222 @@ impl Shuttle {
 
         Ok(bytes)
     }
+
+    /// Errors with a list of the largest files if the archive exceeds the configured max size.
+    /// `compressed` tells whether the size is of the compressed archive or the sum of the file sizes.
+    fn check_archive_size(
+        &self,
+        archive_files: &BTreeMap<PathBuf, PathBuf>,
+        archive_size: u64,
+        compressed: bool,
+    ) -> Result<()> {
+        let Some(max) = self.ctx.deploy_config().and_then(|d| d.max_archive_size) else {
+            return Ok(());
+        };
+        if archive_size <= max {
+            return Ok(());
+        }
+
+        let mut msg = if compressed {
+            format!(
+                "Archive size {} exceeds `max_archive_size` {}. The largest files are:\n",
+                format_size(archive_size),
+                format_size(max),
+            )
+        } else {
+            format!(
+                "Uncompressed size of the files {} exceeds `max_archive_size` {}. \
+                Incremental uploads are checked against the uncompressed size. The largest files are:\n",
+                format_size(archive_size),
+                format_size(max),
+            )
+        };
+        for (name, size) in largest_files(archive_files, 10)? {
+            msg.push_str(&format!(
+                "  {:>10}  {}\n",
+                format_size(size),
+                name.display()
+            ));
+        }
+        msg.push_str(
+            "Exclude files with .gitignore or .ignore, or raise the limit in Shuttle.toml.",
+        );
+
+        bail!(msg)
+    }
+}
+
+/// The first deployment that reached the running state in a list of deployments sorted newest first,
+/// skipping `exclude_id`.
+///
+/// The API has no state history, so stopped deployments are counted as having run:
+/// deployments are stopped when a newer one takes over, while ones that never came up end as failed.
+fn last_healthy_deployment(
+    deployments: Vec<DeploymentResponse>,
+    exclude_id: &str,
+) -> Option<DeploymentResponse> {
+    deployments.into_iter().find(|d| {
+        d.id != exclude_id && matches!(d.state, DeploymentState::Running | DeploymentState::Stopped)
+    })
+}
+
+/// Whether the rollback `target` is the deployment that is currently serving the project,
+/// in which case there is nothing to roll back to
+fn is_serving(target: &DeploymentResponse, current: Option<&DeploymentResponse>) -> bool {
+    target.state == DeploymentState::Running
+        && current.is_some_and(|c| c.id == target.id && c.state == DeploymentState::Running)
+}
+
+/// Build options for running `packages`, one per package if there are several
+fn run_build_options(
+    packages: Vec<String>,
+    args: &CargoBuildArgs,
+    config: Option<&ProjectBuildConfig>,
+) -> Result<Vec<CargoBuildOptions>> {
+    if packages.len() <= 1 {
+        return Ok(vec![cargo_build_options(args, config)?]);
+    }
+    if args.bin.is_some() {
+        bail!("`--bin` can not be used when running multiple packages");
+    }
+
+    let base = cargo_build_options(
+        &CargoBuildArgs {
+            package: vec![],
+            ..args.clone()
+        },
+        config,
+    )?;
+
+    Ok(packages
+        .into_iter()
+        .map(|package| CargoBuildOptions {
+            package: Some(package),
+            bin: None,
+            ..base.clone()
+        })
+        .collect())
+}
+
+/// Build options from the CLI, falling back to the `[build]` section in Shuttle.toml
+fn cargo_build_options(
+    args: &CargoBuildArgs,
+    config: Option<&ProjectBuildConfig>,
+) -> Result<CargoBuildOptions> {
+    if args.package.len() > 1 {
+        bail!("Only one `--package` can be selected for this command");
+    }
+
+    Ok(CargoBuildOptions {
+        package: args
+            .package
+            .first()
+            .cloned()
+            .or_else(|| config.and_then(|c| c.package.clone())),
+        bin: args
+            .bin
+            .clone()
+            .or_else(|| config.and_then(|c| c.bin.clone())),
+        features: if args.features.is_empty() {
+            config.and_then(|c| c.features.clone()).unwrap_or_default()
+        } else {
+            args.features.clone()
+        },
+        no_default_features: args
+            .no_default_features
+            .or_else(|| config.and_then(|c| c.no_default_features)),
+    })
+}
+
+/// Waits for any of the running runtimes to exit, or forever if none are running.
+/// Returns the index of the runtime that exited.
+async fn wait_for_any_runtime(
+    runtimes: &mut [Option<tokio::process::Child>],
+) -> (usize, std::io::Result<std::process::ExitStatus>) {
+    let waits = runtimes
+        .iter_mut()
+        .enumerate()
+        .filter_map(|(i, r)| {
+            r.as_mut()
+                .map(|r| Box::pin(async move { (i, r.wait().await) }))
+        })
+        .collect::<Vec<_>>();
+    if waits.is_empty() {
+        return std::future::pending().await;
+    }
+
+    futures::future::select_all(waits).await.0
+}
+
+/// Waits for the next batch of file changes, or forever if not watching
+async fn next_change(
+    changes: &mut Option<tokio::sync::mpsc::Receiver<Vec<PathBuf>>>,
+) -> Option<Vec<PathBuf>> {
+    match changes.as_mut() {
+        Some(rx) => rx.recv().await,
+        None => std::future::pending().await,
+    }
+}
+
+/// Resolves when the user asks the CLI to terminate
+async fn wait_for_shutdown_signal() {
+    #[cfg(target_family = "unix")]
+    {
+        let mut sigterm_notif =
+            tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())
+                .expect("Can not get the SIGTERM signal receptor");
+        let mut sigint_notif =
+            tokio::signal::unix::signal(tokio::signal::unix::SignalKind::interrupt())
+                .expect("Can not get the SIGINT signal receptor");
+        tokio::select! {
+            _ = sigterm_notif.recv() => {
+                eprintln!("Received SIGTERM. Killing the runtime...");
+            },
+            _ = sigint_notif.recv() => {
+                eprintln!("Received SIGINT. Killing the runtime...");
+            }
+        }
+    }
+    #[cfg(target_family = "windows")]
+    {
+        let mut ctrl_break_notif = tokio::signal::windows::ctrl_break()
+            .expect("Can not get the CtrlBreak signal receptor");
+        let mut ctrl_c_notif =
+            tokio::signal::windows::ctrl_c().expect("Can not get the CtrlC signal receptor");
+        let mut ctrl_close_notif = tokio::signal::windows::ctrl_close()
+            .expect("Can not get the CtrlClose signal receptor");
+        let mut ctrl_logoff_notif = tokio::signal::windows::ctrl_logoff()
+            .expect("Can not get the CtrlLogoff signal receptor");
+        let mut ctrl_shutdown_notif = tokio::signal::windows::ctrl_shutdown()
+            .expect("Can not get the CtrlShutdown signal receptor");
+        tokio::select! {
+            _ = ctrl_break_notif.recv() => {
+                eprintln!("Received ctrl-break.");
+            },
+            _ = ctrl_c_notif.recv() => {
+                eprintln!("Received ctrl-c.");
+            },
+            _ = ctrl_close_notif.recv() => {
+                eprintln!("Received ctrl-close.");
+            },
+            _ = ctrl_logoff_notif.recv() => {
+                eprintln!("Received ctrl-logoff.");
+            },
+            _ = ctrl_shutdown_notif.recv() => {
+                eprintln!("Received ctrl-shutdown.");
+            }
+        }
+    }
+}
+
+/// Get the secrets out of a secrets resource.
+/// A project that never had secrets set has no output on the resource.
+fn secret_store(resource: ResourceResponse) -> Result<SecretStore> {
+    if resource.output.is_null() {
+        return Ok(SecretStore::new(Default::default()));
+    }
+
+    serde_json::from_value(resource.output).context("parsing secrets resource")
 }
 
 /// Calls async function `f` in a loop with `millis` sleep between iterations,
@@ -2112,11 +3356,12 @@ fn create_spinner() -> ProgressBar {
 mod tests {
     use zip::ZipArchive;
 
-    use crate::args::ProjectArgs;
+    use crate::archive::{gather_build_files, ExcludeReason};
+    use crate::args::{ProjectArgs, SecretsArgs};
     use crate::Shuttle;
     use std::fs::{self, canonicalize};
     use std::io::Cursor;
-    use std::path::PathBuf;
+    use std::path::{Path, PathBuf};
 
     pub fn path_from_workspace_root(path: &str) -> PathBuf {
         let path = PathBuf::from(std::env::var("CARGO_MANIFEST_DIR").unwrap())
@@ -2133,7 +3378,9 @@ mod tests {
             .await
             .unwrap();
 
-        let archive = shuttle.make_archive().unwrap();
+        let archive = shuttle
+            .make_archive(&shuttle.gather_build_files().unwrap())
+            .unwrap();
 
         let mut zip = ZipArchive::new(Cursor::new(archive)).unwrap();
         (0..zip.len())
@@ -2185,6 +3432,51 @@ mod tests {
         assert_eq!(entries, expected);
     }
 
+    #[tokio::test]
+    async fn gather_build_files_reports_exclusions() {
+        let working_directory = canonicalize(path_from_workspace_root(
+            "cargo-shuttle/tests/resources/archiving",
+        ))
+        .unwrap();
+
+        fs::write(working_directory.join("Secrets.toml"), "KEY = 'value'").unwrap();
+        fs::create_dir_all(working_directory.join("target")).unwrap();
+        fs::write(working_directory.join("target").join("binary"), b"12345").unwrap();
+
+        let project_args = ProjectArgs {
+            working_directory: working_directory.clone(),
+            name: None,
+            id: Some("proj_archiving-test".to_owned()),
+        };
+        let mut shuttle = Shuttle::new(crate::Binary::Shuttle, None).unwrap();
+        shuttle
+            .load_project(&project_args, false, false)
+            .await
+            .unwrap();
+
+        let files =
+            gather_build_files(shuttle.ctx.project_directory(), shuttle.ctx.include()).unwrap();
+
+        assert_eq!(
+            files.excluded.get(Path::new("Secrets.toml")),
+            Some(&ExcludeReason::Gitignore)
+        );
+        assert_eq!(
+            files.excluded.get(Path::new("asset3")),
+            Some(&ExcludeReason::Gitignore)
+        );
+        // ignored directories are reported once
+        assert_eq!(
+            files.excluded.get(Path::new("target")),
+            Some(&ExcludeReason::DefaultExclude)
+        );
+        assert!(!files.excluded.contains_key(Path::new("target/binary")));
+        assert!(files
+            .included
+            .values()
+            .all(|name| !files.excluded.contains_key(name)));
+    }
+
     #[tokio::test]
     async fn finds_workspace_root() {
         let project_args = ProjectArgs {
@@ -2198,4 +3490,208 @@ mod tests {
             path_from_workspace_root("examples/axum/hello-world")
         );
     }
+
+    #[test]
+    fn get_secrets_profiles() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("Secrets.toml"), "A = 'base'\nB = 'base'").unwrap();
+        fs::write(dir.path().join("Secrets.staging.toml"), "B = 'staging'").unwrap();
+
+        let get = |profile: Option<&str>, layer: bool| {
+            let args = SecretsArgs {
+                profile: profile.map(ToOwned::to_owned),
+                layer,
+                ..Default::default()
+            };
+            Shuttle::get_secrets(&args, dir.path(), false)
+        };
+
+        let base = get(None, false).unwrap().unwrap();
+        assert_eq!(base.get("B").unwrap(), "base");
+
+        let staging = get(Some("staging"), false).unwrap().unwrap();
+        assert_eq!(staging.len(), 1);
+        assert_eq!(staging.get("B").unwrap(), "staging");
+
+        let layered = get(Some("staging"), true).unwrap().unwrap();
+        assert_eq!(layered.get("A").unwrap(), "base");
+        assert_eq!(layered.get("B").unwrap(), "staging");
+
+        assert!(get(Some("prod"), false).is_err());
+    }
+
+    fn deployment(
+        id: &str,
+        state: shuttle_common::models::deployment::DeploymentState,
+    ) -> shuttle_common::models::deployment::DeploymentResponse {
+        use chrono::Utc;
+
+        shuttle_common::models::deployment::DeploymentResponse {
+            id: id.to_owned(),
+            state,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+            uris: vec![],
+            build_id: None,
+            build_meta: None,
+            build_args: None,
+            infra: None,
+            secret_keys: None,
+        }
+    }
+
+    #[test]
+    fn last_healthy_deployment_skips_failed_and_excluded() {
+        use shuttle_common::models::deployment::DeploymentState;
+
+        // newest first
+        let deployments = || {
+            vec![
+                deployment("failed", DeploymentState::Failed),
+                deployment("current", DeploymentState::Running),
+                deployment("stopped", DeploymentState::Stopped),
+                deployment("never-ran", DeploymentState::Failed),
+            ]
+        };
+
+        let found = super::last_healthy_deployment(deployments(), "failed").unwrap();
+        assert_eq!(found.id, "current");
+        let found = super::last_healthy_deployment(deployments(), "current").unwrap();
+        assert_eq!(found.id, "stopped");
+        let mut failed = deployments();
+        failed.retain(|d| d.state == DeploymentState::Failed);
+        assert!(super::last_healthy_deployment(failed, "failed").is_none());
+    }
+
+    #[test]
+    fn rollback_skipped_only_for_serving_deployment() {
+        use shuttle_common::models::deployment::DeploymentState;
+
+        let running = deployment("running", DeploymentState::Running);
+        let stopped = deployment("stopped", DeploymentState::Stopped);
+        let failed = deployment("failed", DeploymentState::Failed);
+
+        // `deploy --rollback-on-failure`: the previous deployment kept serving
+        assert!(super::is_serving(&running, Some(&running)));
+        // `deployment rollback` from the running deployment to a stopped one
+        assert!(!super::is_serving(&stopped, Some(&running)));
+        // the new deployment took over and then failed
+        assert!(!super::is_serving(&stopped, Some(&failed)));
+        assert!(!super::is_serving(&running, Some(&failed)));
+        assert!(!super::is_serving(&stopped, None));
+    }
+
+    #[test]
+    fn cargo_build_options_fall_back_to_config() {
+        use crate::args::CargoBuildArgs;
+        use crate::builder::CargoBuildOptions;
+        use crate::config::ProjectBuildConfig;
+
+        let config = ProjectBuildConfig {
+            package: Some("config-package".to_owned()),
+            bin: Some("config-bin".to_owned()),
+            features: Some(vec!["config-feature".to_owned()]),
+            no_default_features: Some(false),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            super::cargo_build_options(&CargoBuildArgs::default(), None).unwrap(),
+            CargoBuildOptions::default()
+        );
+        assert_eq!(
+            super::cargo_build_options(&CargoBuildArgs::default(), Some(&config)).unwrap(),
+            CargoBuildOptions {
+                package: Some("config-package".to_owned()),
+                bin: Some("config-bin".to_owned()),
+                features: vec!["config-feature".to_owned()],
+                no_default_features: Some(false),
+            }
+        );
+        // arguments override the config field by field
+        let args = CargoBuildArgs {
+            package: vec!["arg-package".to_owned()],
+            features: vec!["arg-feature".to_owned()],
+            no_default_features: Some(true),
+            ..Default::default()
+        };
+        assert_eq!(
+            super::cargo_build_options(&args, Some(&config)).unwrap(),
+            CargoBuildOptions {
+                package: Some("arg-package".to_owned()),
+                bin: Some("config-bin".to_owned()),
+                features: vec!["arg-feature".to_owned()],
+                no_default_features: Some(true),
+            }
+        );
+        let args = CargoBuildArgs {
+            package: vec!["a".to_owned(), "b".to_owned()],
+            ..Default::default()
+        };
+        assert!(super::cargo_build_options(&args, Some(&config)).is_err());
+    }
+
+    #[test]
+    fn run_build_options_per_package() {
+        use crate::args::CargoBuildArgs;
+        use crate::builder::CargoBuildOptions;
+        use crate::config::ProjectBuildConfig;
+
+        let config = ProjectBuildConfig {
+            package: Some("config-package".to_owned()),
+            bin: Some("config-bin".to_owned()),
+            features: Some(vec!["config-feature".to_owned()]),
+            ..Default::default()
+        };
+        let args = CargoBuildArgs {
+            package: vec!["a".to_owned(), "b".to_owned()],
+            ..Default::default()
+        };
+
+        // a single package is built like the other commands
+        let options =
+            super::run_build_options(vec![], &CargoBuildArgs::default(), Some(&config)).unwrap();
+        assert_eq!(options.len(), 1);
+        assert_eq!(options[0].package.as_deref(), Some("config-package"));
+        assert_eq!(options[0].bin.as_deref(), Some("config-bin"));
+
+        // several packages share the features but not the package and binary from the config
+        let options = super::run_build_options(args.package.clone(), &args, Some(&config)).unwrap();
+        assert_eq!(
+            options,
+            ["a", "b"]
+                .map(|package| CargoBuildOptions {
+                    package: Some(package.to_owned()),
+                    bin: None,
+                    features: vec!["config-feature".to_owned()],
+                    no_default_features: None,
+                })
+                .to_vec()
+        );
+
+        let args = CargoBuildArgs {
+            bin: Some("bin".to_owned()),
+            ..args
+        };
+        assert!(super::run_build_options(args.package.clone(), &args, None).is_err());
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn wait_for_any_runtime_returns_first_exit() {
+        let spawn = |script: &str| {
+            Some(
+                tokio::process::Command::new("sh")
+                    .args(["-c", script])
+                    .kill_on_drop(true)
+                    .spawn()
+                    .unwrap(),
+            )
+        };
+        let mut runtimes = vec![spawn("sleep 30"), None, spawn("exit 3")];
+
+        let (index, status) = super::wait_for_any_runtime(&mut runtimes).await;
+        assert_eq!(index, 2);
+        assert_eq!(status.unwrap().code(), Some(3));
+    }
 }
@@ -0,0 +1,277 @@
+use std::collections::HashSet;
+
+use chrono::{DateTime, Utc};
+use shuttle_common::models::log::LogItem;
+
+use crate::args::{LogFilterArgs, LogsArgs, OutputFormat, OutputMode};
+
+/// How log lines are printed
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum LogFormat {
+    /// Human readable, or JSON in the case of `--output json`
+    #[default]
+    Text,
+    /// Newline delimited JSON
+    Ndjson,
+    Logfmt,
+}
+
+impl LogFormat {
+    /// Split the output format into how log lines are printed and how all other output is printed
+    pub fn split_output_format(output: OutputFormat) -> (Self, OutputMode) {
+        match output {
+            OutputFormat::Normal => (Self::Text, OutputMode::Normal),
+            OutputFormat::Json => (Self::Text, OutputMode::Json),
+            OutputFormat::Ndjson => (Self::Ndjson, OutputMode::Json),
+            OutputFormat::Logfmt => (Self::Logfmt, OutputMode::Normal),
+        }
+    }
+
+    /// Whether log lines are printed one structured record per line
+    pub fn is_structured(self) -> bool {
+        self != Self::Text
+    }
+
+    /// Format a log line for printing. `raw` only applies to the text format.
+    pub fn format(self, item: &LogItem, raw: bool) -> String {
+        match self {
+            Self::Text if raw => item.line.clone(),
+            Self::Text => item.to_string(),
+            Self::Ndjson => serde_json::to_string(item).expect("log item to serialize"),
+            Self::Logfmt => format!(
+                "time={} source={} line={}",
+                item.timestamp
+                    .to_rfc3339_opts(chrono::SecondsFormat::Millis, true),
+                logfmt_value(&item.source),
+                logfmt_value(&item.line),
+            ),
+        }
+    }
+}
+
+/// Quote and escape a logfmt value if needed
+fn logfmt_value(value: &str) -> String {
+    if !value.is_empty()
+        && !value
+            .chars()
+            .any(|c| c.is_whitespace() || c.is_control() || c == '"' || c == '=' || c == '\\')
+    {
+        return value.to_owned();
+    }
+
+    let mut quoted = String::with_capacity(value.len() + 2);
+    quoted.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+
+    quoted
+}
+
+/// Which part of the fetched logs to show.
+///
+/// The logs endpoints have no range parameters, so the full set is fetched and sliced client-side.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum LogsRange {
+    Head(u32),
+    Tail(u32),
+    #[default]
+    All,
+}
+
+impl From<&LogsArgs> for LogsRange {
+    fn from(args: &LogsArgs) -> Self {
+        match (args.head, args.tail) {
+            (Some(n), _) => Self::Head(n),
+            (_, Some(n)) => Self::Tail(n),
+            _ => Self::All,
+        }
+    }
+}
+
+impl LogsRange {
+    pub fn apply(self, mut logs: Vec<LogItem>) -> Vec<LogItem> {
+        match self {
+            Self::Head(n) => {
+                logs.truncate(n as usize);
+                logs
+            }
+            Self::Tail(n) => {
+                let skip = logs.len().saturating_sub(n as usize);
+                logs.split_off(skip)
+            }
+            Self::All => logs,
+        }
+    }
+}
+
+impl LogFilterArgs {
+    /// Whether any filter is set
+    pub fn is_empty(&self) -> bool {
+        self.since.is_none()
+            && self.until.is_none()
+            && self.grep.is_none()
+            && self.sources.is_empty()
+    }
+
+    /// Whether a log line should be shown.
+    ///
+    /// The time window always applies, while `invert` flips the result of the pattern and source filters.
+    pub fn matches(&self, item: &LogItem) -> bool {
+        if self.since.is_some_and(|since| item.timestamp < since)
+            || self.until.is_some_and(|until| item.timestamp > until)
+        {
+            return false;
+        }
+        if self.grep.is_none() && self.sources.is_empty() {
+            return true;
+        }
+
+        let matches = self.grep.as_ref().is_none_or(|re| re.is_match(&item.line))
+            && (self.sources.is_empty() || self.sources.contains(&item.source));
+
+        matches != self.invert
+    }
+}
+
+/// Remembers which log lines have already been printed, so that lines that are
+/// replayed by the server after a reconnect are not printed twice.
+///
+/// Lines are assumed to arrive in timestamp order, so only the lines that share
+/// the latest seen timestamp need to be kept around.
+#[derive(Default)]
+pub struct LogDeduplicator {
+    latest: Option<DateTime<Utc>>,
+    seen_at_latest: HashSet<(String, String)>,
+}
+
+impl LogDeduplicator {
+    /// Returns true if this log line has not been seen before
+    pub fn is_new(&mut self, item: &LogItem) -> bool {
+        match self.latest {
+            Some(latest) if item.timestamp < latest => false,
+            Some(latest) if item.timestamp == latest => self
+                .seen_at_latest
+                .insert((item.source.clone(), item.line.clone())),
+            _ => {
+                self.latest = Some(item.timestamp);
+                self.seen_at_latest.clear();
+                self.seen_at_latest
+                    .insert((item.source.clone(), item.line.clone()));
+                true
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+    use regex::Regex;
+
+    use super::*;
+
+    fn item(secs: i64, line: &str) -> LogItem {
+        LogItem::new(
+            Utc.timestamp_opt(secs, 0).unwrap(),
+            "app".to_owned(),
+            line.to_owned(),
+        )
+    }
+
+    #[test]
+    fn formats() {
+        let log = LogItem::new(
+            Utc.timestamp_opt(1, 0).unwrap(),
+            "app".to_owned(),
+            r#"GET /a "b" key=value"#.to_owned(),
+        );
+
+        assert_eq!(
+            LogFormat::Ndjson.format(&log, false),
+            r#"{"timestamp":"1970-01-01T00:00:01Z","source":"app","line":"GET /a \"b\" key=value"}"#
+        );
+        assert_eq!(
+            LogFormat::Logfmt.format(&log, false),
+            r#"time=1970-01-01T00:00:01.000Z source=app line="GET /a \"b\" key=value""#
+        );
+        assert_eq!(LogFormat::Text.format(&log, true), log.line);
+        assert_eq!(logfmt_value(""), r#""""#);
+        assert_eq!(logfmt_value("a\nb"), r#""a\nb""#);
+    }
+
+    #[test]
+    fn range() {
+        let logs = (0..5).map(|i| item(i, "")).collect::<Vec<_>>();
+
+        assert_eq!(LogsRange::Head(2).apply(logs.clone()).len(), 2);
+        assert_eq!(LogsRange::Head(10).apply(logs.clone()).len(), 5);
+        let tail = LogsRange::Tail(2).apply(logs.clone());
+        assert_eq!(
+            tail.iter()
+                .map(|l| l.timestamp.timestamp())
+                .collect::<Vec<_>>(),
+            vec![3, 4]
+        );
+        assert_eq!(LogsRange::All.apply(logs).len(), 5);
+    }
+
+    #[test]
+    fn filter() {
+        let filter = LogFilterArgs {
+            since: Some(Utc.timestamp_opt(2, 0).unwrap()),
+            until: Some(Utc.timestamp_opt(4, 0).unwrap()),
+            ..Default::default()
+        };
+        assert!(!filter.matches(&item(1, "")));
+        assert!(filter.matches(&item(2, "")));
+        assert!(filter.matches(&item(4, "")));
+        assert!(!filter.matches(&item(5, "")));
+
+        let filter = LogFilterArgs {
+            grep: Some(Regex::new("^ERROR").unwrap()),
+            sources: vec!["app".to_owned()],
+            ..Default::default()
+        };
+        assert!(filter.matches(&item(1, "ERROR boom")));
+        assert!(!filter.matches(&item(1, "INFO ok")));
+        let mut build = item(2, "ERROR boom");
+        build.source = "build".to_owned();
+        assert!(!filter.matches(&build));
+
+        let filter = LogFilterArgs {
+            invert: true,
+            since: Some(Utc.timestamp_opt(2, 0).unwrap()),
+            ..filter
+        };
+        assert!(!filter.matches(&item(2, "ERROR boom")));
+        assert!(filter.matches(&item(2, "INFO ok")));
+        assert!(filter.matches(&build));
+        assert!(!filter.matches(&item(1, "INFO ok")));
+    }
+
+    #[test]
+    fn deduplicates_replayed_lines() {
+        let mut dedup = LogDeduplicator::default();
+
+        assert!(dedup.is_new(&item(1, "a")));
+        assert!(dedup.is_new(&item(2, "b")));
+        assert!(dedup.is_new(&item(2, "c")));
+
+        // replayed after a reconnect
+        assert!(!dedup.is_new(&item(1, "a")));
+        assert!(!dedup.is_new(&item(2, "b")));
+        assert!(!dedup.is_new(&item(2, "c")));
+
+        assert!(dedup.is_new(&item(2, "d")));
+        assert!(dedup.is_new(&item(3, "b")));
+    }
+}
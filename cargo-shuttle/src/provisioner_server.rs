@@ -1,16 +1,32 @@
 use std::{
-    collections::HashMap, convert::Infallible, io::stderr, net::SocketAddr, process::exit,
-    sync::Arc, time::Duration,
+    collections::{BTreeMap, BTreeSet, HashMap},
+    convert::Infallible,
+    io::{stderr, Write},
+    net::SocketAddr,
+    path::{Path, PathBuf},
+    process::exit,
+    sync::Arc,
+    time::{Duration, Instant},
 };
 
 use anyhow::{bail, Context, Result};
 use bollard::{
-    container::{Config, CreateContainerOptions, StartContainerOptions},
-    exec::{CreateExecOptions, CreateExecResults},
+    container::{
+        Config, CreateContainerOptions, ListContainersOptions, LogOutput, LogsOptions,
+        RemoveContainerOptions, StartContainerOptions,
+    },
+    exec::{CreateExecOptions, CreateExecResults, StartExecResults},
     image::CreateImageOptions,
-    models::{CreateImageInfo, HostConfig, PortBinding, ProgressDetail},
+    models::{
+        ContainerSummary, CreateImageInfo, HostConfig, MountPointTypeEnum, PortBinding,
+        ProgressDetail,
+    },
     service::ContainerInspectResponse,
-    Docker,
+    Docker, API_DEFAULT_VERSION,
+};
+use comfy_table::{
+    presets::{NOTHING, UTF8_BORDERS_ONLY},
+    Attribute, Cell, Color, ContentArrangement, Table,
 };
 use crossterm::{
     cursor::{MoveDown, MoveUp},
@@ -18,63 +34,137 @@ use crossterm::{
     QueueableCommand,
 };
 use futures::StreamExt;
-use http_body_util::{combinators::BoxBody, BodyExt, Empty, Full};
+use http_body_util::{combinators::BoxBody, BodyExt, Full};
 use hyper::{
     body::{self, Bytes},
     server::conn::http1,
     service::service_fn,
-    Method, Request as HyperRequest, Response,
+    Method, Request as HyperRequest, Response, StatusCode,
 };
 use hyper_util::rt::TokioIo;
 use portpicker::pick_unused_port;
+use serde::Serialize;
 use shuttle_common::{
+    models::error::ApiError,
     models::resource::{
         self, ProvisionResourceRequest, ResourceResponse, ResourceState, ResourceType,
     },
     secrets::Secret,
     tables::get_resource_tables,
-    ContainerRequest, ContainerResponse, DatabaseInfo, DbInput,
+    ContainerRequest, ContainerResponse, DatabaseInfo, DbInput, ReadinessCheck,
 };
-use tokio::{net::TcpListener, time::sleep};
-use tracing::{debug, error, trace};
+use tokio::{
+    io::{AsyncRead, AsyncWriteExt},
+    net::{TcpListener, TcpStream},
+    time::sleep,
+};
+use tracing::{debug, trace};
+
+use crate::config::{LocalResourceConfig, ProjectLocalConfig, PullPolicy};
+
+/// Things that can go wrong when provisioning local containers
+#[derive(Debug, thiserror::Error)]
+pub enum LocalProvisionerError {
+    #[error("Could not connect to the container engine: {0}")]
+    DaemonUnreachable(bollard::errors::Error),
+    #[error("Failed to pull image '{image}': {message}")]
+    ImagePullFailed { image: String, message: String },
+    #[error("Container '{container}' could not get a port: {message}")]
+    PortConflict { container: String, message: String },
+    #[error("Container '{container}' is in an unexpected state: {reason}")]
+    UnexpectedState { container: String, reason: String },
+    #[error("Container '{container}' did not become ready within {} seconds", timeout.as_secs())]
+    ReadinessTimeout {
+        container: String,
+        timeout: Duration,
+    },
+}
+
+impl LocalProvisionerError {
+    /// Classify an error returned by the container engine for a request about a container
+    fn docker(container: &str, error: bollard::errors::Error) -> Self {
+        match error {
+            bollard::errors::Error::DockerResponseServerError { message, .. }
+                if message.contains("port is already allocated")
+                    || message.contains("address already in use") =>
+            {
+                Self::PortConflict {
+                    container: container.to_string(),
+                    message,
+                }
+            }
+            bollard::errors::Error::DockerResponseServerError { message, .. } => {
+                Self::UnexpectedState {
+                    container: container.to_string(),
+                    reason: message,
+                }
+            }
+            e => Self::DaemonUnreachable(e),
+        }
+    }
+
+    /// What the user can do about the error
+    pub fn hint(&self) -> String {
+        match self {
+            Self::DaemonUnreachable(_) => "Make sure Docker or another container engine is installed and running, \
+                or set `docker_host` in the `[local]` section of Shuttle.toml. \
+                For more help: https://docs.shuttle.dev/docs/local-run#docker-engines"
+                .to_string(),
+            Self::ImagePullFailed { .. } => "Check the image name and your network connection. \
+                The image and pull policy can be changed in the `[local]` section of Shuttle.toml."
+                .to_string(),
+            Self::PortConflict { .. } => "Another process is using the port. Stop it, \
+                or remove the container with `shuttle local down` so that it gets a new port on the next `shuttle run`."
+                .to_string(),
+            Self::UnexpectedState { .. } => "Remove the container with `shuttle local down`, \
+                and it will be created again on the next `shuttle run`."
+                .to_string(),
+            Self::ReadinessTimeout { container, .. } => {
+                format!("Check the logs of the container with `docker logs {container}`.")
+            }
+        }
+    }
+}
 
 /// A provisioner for local runs
 /// It uses Docker to create Databases
 pub struct LocalProvisioner {
     docker: Docker,
+    config: ProjectLocalConfig,
 }
 
 impl LocalProvisioner {
-    pub fn new() -> Result<Self> {
-        // This only constructs the client and does not try to connect.
-        // If the socket is not found, a "no such file" error will happen on the first request to Docker.
-        Ok(Self {
-            docker: Docker::connect_with_defaults()?,
-        })
+    pub fn new(config: ProjectLocalConfig) -> Result<Self, LocalProvisionerError> {
+        let docker = connect_docker(config.docker_host.as_deref())?;
+
+        Ok(Self { docker, config })
+    }
+
+    /// The configured image for a resource, or the default one
+    fn image(&self, key: &str, default: String) -> String {
+        self.config
+            .resource(key)
+            .and_then(|r| r.image.clone())
+            .unwrap_or(default)
     }
 
     fn get_container_first_host_port(
         &self,
         container: &ContainerInspectResponse,
         port: &str,
-    ) -> String {
+    ) -> Result<String, LocalProvisionerError> {
         container
             .host_config
             .as_ref()
-            .expect("container to have host config")
-            .port_bindings
-            .as_ref()
-            .expect("port bindings on container")
-            .get(port)
-            .expect("a port bindings entry")
-            .as_ref()
-            .expect("a port bindings")
-            .first()
-            .expect("at least one port binding")
-            .host_port
-            .as_ref()
-            .expect("a host port")
-            .clone()
+            .and_then(|c| c.port_bindings.as_ref())
+            .and_then(|b| b.get(port))
+            .and_then(|b| b.as_ref())
+            .and_then(|b| b.first())
+            .and_then(|b| b.host_port.clone())
+            .ok_or_else(|| LocalProvisionerError::UnexpectedState {
+                container: container.name.clone().unwrap_or_default(),
+                reason: format!("port {port} is not published to the host"),
+            })
     }
 
     async fn start_container_if_not_running(
@@ -82,60 +172,73 @@ impl LocalProvisioner {
         container: &ContainerInspectResponse,
         container_type: &str,
         name: &str,
-    ) {
-        if !container
-            .state
-            .as_ref()
-            .expect("container to have a state")
-            .running
-            .expect("state to have a running key")
-        {
+    ) -> Result<(), LocalProvisionerError> {
+        let Some(running) = container.state.as_ref().and_then(|s| s.running) else {
+            return Err(LocalProvisionerError::UnexpectedState {
+                container: name.to_string(),
+                reason: "the container engine did not report its state".to_string(),
+            });
+        };
+        if !running {
             trace!("{container_type} container '{name}' not running, so starting it");
             self.docker
                 .start_container(name, None::<StartContainerOptions<String>>)
                 .await
-                .expect("failed to start container");
+                .map_err(|e| LocalProvisionerError::docker(name, e))?;
         }
+
+        Ok(())
     }
 
+    /// Get the container with this name, or create it. Also returns whether it was created.
     async fn get_container(
         &self,
         container_name: &str,
-        image: &str,
-        port: &str,
-        env: Option<Vec<String>>,
-    ) -> Result<ContainerInspectResponse> {
+        new: NewContainer,
+    ) -> Result<(ContainerInspectResponse, bool), LocalProvisionerError> {
         match self.docker.inspect_container(container_name, None).await {
             Ok(container) => {
                 trace!("found container {container_name}");
-                Ok(container)
+                Ok((container, false))
             }
             Err(bollard::errors::Error::DockerResponseServerError {
                 status_code: 404, ..
             }) => {
-                self.pull_image(image).await.expect("failed to pull image");
+                self.ensure_image(&new.image).await?;
                 trace!("will create container {container_name}");
                 let options = Some(CreateContainerOptions {
                     name: container_name,
                     platform: None,
                 });
                 let mut port_bindings = HashMap::new();
-                let host_port = pick_unused_port().expect("system to have a free port");
-                port_bindings.insert(
-                    port.to_string(),
-                    Some(vec![PortBinding {
-                        host_port: Some(host_port.to_string()),
-                        ..Default::default()
-                    }]),
-                );
+                for port in &new.ports {
+                    let host_port =
+                        pick_unused_port().ok_or_else(|| LocalProvisionerError::PortConflict {
+                            container: container_name.to_string(),
+                            message: "no free port was found on this machine".to_string(),
+                        })?;
+                    port_bindings.insert(
+                        port.to_string(),
+                        Some(vec![PortBinding {
+                            host_port: Some(host_port.to_string()),
+                            ..Default::default()
+                        }]),
+                    );
+                }
                 let host_config = HostConfig {
                     port_bindings: Some(port_bindings),
+                    binds: new.binds,
                     ..Default::default()
                 };
 
                 let config: Config<String> = Config {
-                    image: Some(image.to_string()),
-                    env,
+                    image: Some(new.image),
+                    env: new.env,
+                    cmd: new.cmd,
+                    labels: Some(new.labels),
+                    exposed_ports: Some(
+                        new.ports.into_iter().map(|p| (p, HashMap::new())).collect(),
+                    ),
                     host_config: Some(host_config),
                     ..Default::default()
                 };
@@ -143,21 +246,17 @@ impl LocalProvisioner {
                 self.docker
                     .create_container(options, config)
                     .await
-                    .expect("to be able to create container");
+                    .map_err(|e| LocalProvisionerError::docker(container_name, e))?;
 
-                Ok(self
+                let container = self
                     .docker
                     .inspect_container(container_name, None)
                     .await
-                    .expect("container to be created"))
-            }
-            Err(error) => {
-                error!("Got unexpected error while inspecting docker container: {error}");
-                error!(
-                    "Make sure Docker is installed and running. For more help: https://docs.shuttle.dev/docs/local-run#docker-engines"
-                );
-                Err(anyhow::anyhow!("{}", error))
+                    .map_err(|e| LocalProvisionerError::docker(container_name, e))?;
+
+                Ok((container, true))
             }
+            Err(error) => Err(LocalProvisionerError::docker(container_name, error)),
         }
     }
 
@@ -166,6 +265,7 @@ impl LocalProvisioner {
         project_name: &str,
         db_type: ResourceType,
         db_name: Option<String>,
+        seed: Option<PathBuf>,
     ) -> Result<DatabaseInfo> {
         trace!("getting sql string for project '{project_name}'");
 
@@ -178,6 +278,8 @@ impl LocalProvisioner {
             _ => project_name.to_string(),
         };
 
+        let engine_config = db_type_to_config(db_type.clone(), &database_name);
+        let seed_cmd = restore_cmd(&engine_config, &database_name);
         let EngineConfig {
             r#type,
             image,
@@ -187,17 +289,44 @@ impl LocalProvisioner {
             port,
             env,
             is_ready_cmd,
-        } = db_type_to_config(db_type, &database_name);
+            data_dir,
+        } = engine_config;
+        let image = self.image(db_type.as_ref(), image);
         let container_name = format!("shuttle_{project_name}_{type}");
+        let volume = volume_name(&container_name, self.config.resource(db_type.as_ref()));
 
-        let container = self
-            .get_container(&container_name, &image, &port, env)
+        let (container, _) = self
+            .get_container(
+                &container_name,
+                NewContainer {
+                    image,
+                    ports: vec![port.clone()],
+                    env,
+                    binds: volume.as_ref().map(|v| vec![format!("{v}:{data_dir}")]),
+                    cmd: None,
+                    labels: container_labels(project_name, db_type.as_ref()),
+                },
+            )
             .await?;
 
-        let host_port = self.get_container_first_host_port(&container, &port);
+        if let Some(volume) = volume.as_ref() {
+            let mounted = container
+                .mounts
+                .iter()
+                .flatten()
+                .any(|m| m.name.as_ref() == Some(volume));
+            if !mounted {
+                eprintln!(
+                    "WARNING: The {db_type} container was created without the volume '{volume}'. \
+                    Run `shuttle resource reset --local {db_type}` to recreate it with the volume."
+                );
+            }
+        }
+
+        let host_port = self.get_container_first_host_port(&container, &port)?;
 
         self.start_container_if_not_running(&container, &r#type, &container_name)
-            .await;
+            .await?;
 
         self.wait_for_ready(&container_name, is_ready_cmd.clone())
             .await?;
@@ -207,6 +336,14 @@ impl LocalProvisioner {
         sleep(Duration::from_millis(450)).await;
         self.wait_for_ready(&container_name, is_ready_cmd).await?;
 
+        // Runs on every start until the seed marker is written, so that a failed seed is retried
+        // and a seed added to an existing database is applied
+        if let Some(seed) = seed {
+            self.seed_database(&container_name, &data_dir, &seed, seed_cmd)
+                .await
+                .with_context(|| format!("seeding {db_type} from {}", seed.display()))?;
+        }
+
         let res = DatabaseInfo::new(
             engine,
             username,
@@ -227,24 +364,491 @@ impl LocalProvisioner {
             env,
             image,
             port,
+            extra_ports,
+            command,
+            volumes,
+            readiness,
         } = req;
 
+        let image = self.image(&container_name, image);
+        let labels = container_labels(&project_name, &container_name);
         let container_name = format!("shuttle_{project_name}_{container_name}");
+        let ports = std::iter::once(port.clone())
+            .chain(extra_ports)
+            .collect::<Vec<_>>();
 
-        let container = self
-            .get_container(&container_name, &image, &port, Some(env))
+        let (container, created) = self
+            .get_container(
+                &container_name,
+                NewContainer {
+                    image,
+                    ports: ports.clone(),
+                    env: Some(env),
+                    binds: (!volumes.is_empty()).then_some(volumes),
+                    cmd: command,
+                    labels,
+                },
+            )
             .await?;
 
-        let host_port = self.get_container_first_host_port(&container, &port);
+        let mut host_ports = BTreeMap::new();
+        for p in ports {
+            match self.get_container_first_host_port(&container, &p) {
+                Ok(host_port) => {
+                    host_ports.insert(p, host_port);
+                }
+                // Containers created by older versions only publish the ports that were requested back then
+                Err(_) if !created && p != port => {
+                    eprintln!(
+                        "WARNING: The container '{container_name}' does not publish port {p}. \
+                        Remove it with `shuttle local down` to recreate it with all ports."
+                    );
+                }
+                Err(error) => return Err(error.into()),
+            }
+        }
 
         self.start_container_if_not_running(&container, &container_name, &container_name)
-            .await;
+            .await?;
+
+        if let Some(check) = readiness {
+            let check = published_check(check, &host_ports, &port);
+            self.wait_for_check(&container_name, &check, &host_ports)
+                .await?;
+        }
+
+        Ok(ContainerResponse {
+            host_port: host_ports[&port].clone(),
+            host_ports,
+        })
+    }
+
+    /// Wait until the readiness check of a container passes
+    async fn wait_for_check(
+        &self,
+        container_name: &str,
+        check: &ReadinessCheck,
+        host_ports: &BTreeMap<String, String>,
+    ) -> Result<(), LocalProvisionerError> {
+        let host_port = |port: &String| {
+            host_ports
+                .get(port)
+                .cloned()
+                .ok_or_else(|| LocalProvisionerError::UnexpectedState {
+                    container: container_name.to_string(),
+                    reason: format!("the readiness check uses port {port}, which is not exposed"),
+                })
+        };
+        let client = reqwest::Client::builder()
+            .timeout(READY_CHECK_TIMEOUT)
+            .build()
+            .expect("to build a client for readiness checks");
+        let started = Instant::now();
+        loop {
+            trace!("checking if '{container_name}' is ready");
+            let ready = match check {
+                ReadinessCheck::Exec { command } => self
+                    .exec_with_io(container_name, command.clone(), None, &mut std::io::sink())
+                    .await
+                    .is_ok(),
+                ReadinessCheck::Tcp { port } => {
+                    TcpStream::connect(format!("localhost:{}", host_port(port)?))
+                        .await
+                        .is_ok()
+                }
+                ReadinessCheck::Http { port, path } => client
+                    .get(format!("http://localhost:{}{path}", host_port(port)?))
+                    .send()
+                    .await
+                    .is_ok_and(|r| r.status().is_success()),
+            };
+            if ready {
+                return Ok(());
+            }
+            if started.elapsed() > READY_TIMEOUT {
+                return Err(LocalProvisionerError::ReadinessTimeout {
+                    container: container_name.to_string(),
+                    timeout: READY_TIMEOUT,
+                });
+            }
+
+            sleep(Duration::from_millis(500)).await;
+        }
+    }
+
+    /// Remove the database container of this type and its volume, so that the next run starts with an empty database
+    pub async fn reset_database(&self, project_name: &str, db_type: ResourceType) -> Result<()> {
+        if !is_database(&db_type) {
+            bail!("Only database resources can be reset locally, got {db_type}");
+        }
+        let config = self.config.resource(db_type.as_ref());
+        let EngineConfig { r#type, .. } = db_type_to_config(db_type, project_name);
+        let container_name = format!("shuttle_{project_name}_{type}");
+
+        match self
+            .docker
+            .remove_container(
+                &container_name,
+                Some(RemoveContainerOptions {
+                    // also removes the anonymous volume that is used when no named volume is configured
+                    v: true,
+                    force: true,
+                    ..Default::default()
+                }),
+            )
+            .await
+        {
+            Ok(()) => eprintln!("Removed container {container_name}"),
+            Err(bollard::errors::Error::DockerResponseServerError {
+                status_code: 404, ..
+            }) => trace!("container {container_name} not found"),
+            Err(e) => return Err(e).context("removing container"),
+        }
+
+        if let Some(volume) = volume_name(&container_name, config) {
+            match self.docker.remove_volume(&volume, None).await {
+                Ok(()) => eprintln!("Removed volume {volume}"),
+                Err(bollard::errors::Error::DockerResponseServerError {
+                    status_code: 404, ..
+                }) => trace!("volume {volume} not found"),
+                Err(e) => return Err(e).context("removing volume"),
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Run the seed files against a new database, unless its data directory says that it was seeded before
+    async fn seed_database(
+        &self,
+        container_name: &str,
+        data_dir: &str,
+        seed: &Path,
+        seed_cmd: Vec<String>,
+    ) -> Result<()> {
+        let marker = format!("{data_dir}/{SEED_MARKER}");
+        // The marker lives in the data directory, so that a volume that was seeded before is not seeded again
+        let seeded = self
+            .exec_with_io(
+                container_name,
+                vec!["test".to_string(), "-f".to_string(), marker.clone()],
+                None,
+                &mut std::io::sink(),
+            )
+            .await
+            .is_ok();
+        if seeded {
+            trace!("'{container_name}' was already seeded");
+            return Ok(());
+        }
+
+        for file in seed_files(seed)? {
+            eprintln!("Seeding {container_name} with {}", file.display());
+            let mut input = tokio::fs::File::open(&file)
+                .await
+                .with_context(|| format!("opening {}", file.display()))?;
+            self.exec_with_io(
+                container_name,
+                seed_cmd.clone(),
+                Some(&mut input),
+                &mut std::io::sink(),
+            )
+            .await
+            .with_context(|| format!("running {}", file.display()))?;
+        }
+
+        self.exec_with_io(
+            container_name,
+            vec!["touch".to_string(), marker],
+            None,
+            &mut std::io::sink(),
+        )
+        .await
+        .context("writing seed marker")
+    }
+
+    /// Containers that were provisioned for a project, optionally only the one for a resource
+    pub async fn list_containers(
+        &self,
+        project_name: &str,
+        resource: Option<&str>,
+    ) -> Result<Vec<(LocalContainer, ContainerSummary)>> {
+        // Containers from older versions have no labels, so filtering happens here instead of in the engine
+        let containers = self
+            .docker
+            .list_containers(Some(ListContainersOptions::<String> {
+                all: true,
+                ..Default::default()
+            }))
+            .await
+            .context("listing containers")?
+            .into_iter()
+            .filter_map(|c| Some((LocalContainer::for_project(&c, project_name)?, c)))
+            .collect();
+
+        select_resource(containers, resource)
+    }
+
+    pub async fn stop_containers(&self, project_name: &str, resource: Option<&str>) -> Result<()> {
+        for (local, _) in self.list_containers(project_name, resource).await? {
+            if local.state != "running" {
+                continue;
+            }
+            self.docker
+                .stop_container(&local.name, None)
+                .await
+                .with_context(|| format!("stopping container {}", local.name))?;
+            eprintln!("Stopped {} ({})", local.name, local.resource);
+        }
+
+        Ok(())
+    }
+
+    /// Remove the containers of a project, and optionally all volumes they use
+    pub async fn remove_containers(
+        &self,
+        project_name: &str,
+        resource: Option<&str>,
+        volumes: bool,
+    ) -> Result<()> {
+        for (local, container) in self.list_containers(project_name, resource).await? {
+            self.docker
+                .remove_container(
+                    &local.name,
+                    Some(RemoveContainerOptions {
+                        v: volumes,
+                        force: true,
+                        ..Default::default()
+                    }),
+                )
+                .await
+                .with_context(|| format!("removing container {}", local.name))?;
+            eprintln!("Removed {} ({})", local.name, local.resource);
+
+            if !volumes {
+                continue;
+            }
+            // Anonymous volumes are removed with the container, named ones are not
+            let named_volumes = container
+                .mounts
+                .iter()
+                .flatten()
+                .filter(|m| m.typ == Some(MountPointTypeEnum::VOLUME))
+                .filter_map(|m| m.name.as_deref());
+            for volume in named_volumes {
+                match self.docker.remove_volume(volume, None).await {
+                    Ok(()) => eprintln!("Removed volume {volume}"),
+                    Err(bollard::errors::Error::DockerResponseServerError {
+                        status_code: 404,
+                        ..
+                    }) => trace!("volume {volume} not found"),
+                    Err(e) => return Err(e).context("removing volume"),
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Print the logs of a resource container
+    pub async fn container_logs(
+        &self,
+        project_name: &str,
+        resource: &str,
+        follow: bool,
+        tail: Option<u32>,
+    ) -> Result<()> {
+        let Some((LocalContainer { name, .. }, _)) = self
+            .list_containers(project_name, Some(resource))
+            .await?
+            .into_iter()
+            .next()
+        else {
+            bail!("No local container found for '{resource}'");
+        };
+
+        let mut logs = self.docker.logs(
+            &name,
+            Some(LogsOptions::<String> {
+                follow,
+                stdout: true,
+                stderr: true,
+                tail: tail.map_or_else(|| "all".to_string(), |n| n.to_string()),
+                ..Default::default()
+            }),
+        );
+        while let Some(line) = logs.next().await {
+            match line.context("reading container logs")? {
+                LogOutput::StdErr { message } => stderr().write_all(&message)?,
+                output => std::io::stdout().write_all(&output.into_bytes())?,
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Write an SQL dump of a local database to `out`
+    pub async fn dump_database(
+        &self,
+        project_name: &str,
+        db_type: ResourceType,
+        db_name: Option<String>,
+        out: &mut (dyn Write + Send),
+    ) -> Result<()> {
+        let (container_name, config, database_name) = self
+            .running_database(project_name, db_type, db_name)
+            .await?;
+
+        self.exec_with_io(
+            &container_name,
+            dump_cmd(&config, &database_name),
+            None,
+            out,
+        )
+        .await
+        .context("dumping database")
+    }
+
+    /// Load an SQL dump into a local database
+    pub async fn restore_database(
+        &self,
+        project_name: &str,
+        db_type: ResourceType,
+        db_name: Option<String>,
+        dump: &mut (dyn AsyncRead + Unpin + Send),
+    ) -> Result<()> {
+        let (container_name, config, database_name) = self
+            .running_database(project_name, db_type, db_name)
+            .await?;
+
+        self.exec_with_io(
+            &container_name,
+            restore_cmd(&config, &database_name),
+            Some(dump),
+            &mut std::io::sink(),
+        )
+        .await
+        .context("restoring database")
+    }
+
+    /// Find the container of an existing local database and make sure it is ready for connections
+    async fn running_database(
+        &self,
+        project_name: &str,
+        db_type: ResourceType,
+        db_name: Option<String>,
+    ) -> Result<(String, EngineConfig, String)> {
+        if !is_database(&db_type) {
+            bail!("Only database resources can be dumped or restored locally, got {db_type}");
+        }
+        let database_name = db_name.unwrap_or_else(|| project_name.to_string());
+        let config = db_type_to_config(db_type.clone(), &database_name);
+        let container_name = format!("shuttle_{project_name}_{}", config.r#type);
+
+        let container = match self.docker.inspect_container(&container_name, None).await {
+            Ok(container) => container,
+            Err(bollard::errors::Error::DockerResponseServerError {
+                status_code: 404, ..
+            }) => bail!(
+                "No local {db_type} container found for this project. Run `shuttle run` to create it."
+            ),
+            Err(e) => return Err(e).context("inspecting container"),
+        };
+        self.start_container_if_not_running(&container, &config.r#type, &container_name)
+            .await?;
+        self.wait_for_ready(&container_name, config.is_ready_cmd.clone())
+            .await?;
+
+        Ok((container_name, config, database_name))
+    }
+
+    /// Run a command in a container, streaming `stdin` into it and its stdout to `out`
+    async fn exec_with_io(
+        &self,
+        container_name: &str,
+        cmd: Vec<String>,
+        stdin: Option<&mut (dyn AsyncRead + Unpin + Send)>,
+        out: &mut (dyn Write + Send),
+    ) -> Result<()> {
+        let config = CreateExecOptions {
+            cmd: Some(cmd),
+            attach_stdin: Some(stdin.is_some()),
+            attach_stdout: Some(true),
+            attach_stderr: Some(true),
+            ..Default::default()
+        };
+        let CreateExecResults { id } = self
+            .docker
+            .create_exec(container_name, config)
+            .await
+            .context("creating exec")?;
+
+        let StartExecResults::Attached {
+            mut output,
+            mut input,
+        } = self
+            .docker
+            .start_exec(&id, None)
+            .await
+            .context("starting exec")?
+        else {
+            bail!("exec was not attached");
+        };
+
+        let mut stderr_output = Vec::new();
+        let write = async {
+            if let Some(stdin) = stdin {
+                tokio::io::copy(stdin, &mut input).await?;
+                // closes stdin of the command
+                input.shutdown().await?;
+            }
+            Ok::<_, std::io::Error>(())
+        };
+        let read = async {
+            while let Some(chunk) = output.next().await {
+                match chunk? {
+                    LogOutput::StdOut { message } => out.write_all(&message)?,
+                    LogOutput::StdErr { message } => stderr_output.extend_from_slice(&message),
+                    _ => {}
+                }
+            }
+            Ok::<_, anyhow::Error>(())
+        };
+        let (write, read) = tokio::join!(write, read);
+        read?;
+        write.context("writing to the container")?;
+        out.flush()?;
 
-        Ok(ContainerResponse { host_port })
+        let exit_code = self
+            .docker
+            .inspect_exec(&id)
+            .await
+            .context("inspecting exec")?
+            .exit_code;
+        if exit_code.is_some_and(|code| code != 0) {
+            bail!(
+                "command exited with code {}: {}",
+                exit_code.unwrap_or_default(),
+                String::from_utf8_lossy(&stderr_output).trim_end()
+            );
+        }
+
+        Ok(())
     }
 
-    async fn wait_for_ready(&self, container_name: &str, is_ready_cmd: Vec<String>) -> Result<()> {
+    async fn wait_for_ready(
+        &self,
+        container_name: &str,
+        is_ready_cmd: Vec<String>,
+    ) -> Result<(), LocalProvisionerError> {
+        let started = Instant::now();
         loop {
+            if started.elapsed() > READY_TIMEOUT {
+                return Err(LocalProvisionerError::ReadinessTimeout {
+                    container: container_name.to_string(),
+                    timeout: READY_TIMEOUT,
+                });
+            }
             trace!("waiting for '{container_name}' to be ready for connections");
 
             let config = CreateExecOptions {
@@ -258,21 +862,19 @@ impl LocalProvisioner {
                 .docker
                 .create_exec(container_name, config)
                 .await
-                .expect("failed to create exec to check if container is ready");
+                .map_err(|e| LocalProvisionerError::docker(container_name, e))?;
 
             let ready_result = self
                 .docker
                 .start_exec(&id, None)
                 .await
-                .expect("failed to execute ready command");
+                .map_err(|e| LocalProvisionerError::docker(container_name, e))?;
 
-            if let bollard::exec::StartExecResults::Attached { mut output, .. } = ready_result {
+            if let StartExecResults::Attached { mut output, .. } = ready_result {
                 while let Some(line) = output.next().await {
                     trace!("line: {:?}", line);
 
-                    if let bollard::container::LogOutput::StdOut { .. } =
-                        line.expect("output to have a log line")
-                    {
+                    if let Ok(LogOutput::StdOut { .. }) = line {
                         return Ok(());
                     }
                 }
@@ -282,7 +884,26 @@ impl LocalProvisioner {
         }
     }
 
-    async fn pull_image(&self, image: &str) -> Result<(), String> {
+    /// Make the image available according to the pull policy
+    async fn ensure_image(&self, image: &str) -> Result<(), LocalProvisionerError> {
+        let policy = self.config.pull_policy.unwrap_or_default();
+        if policy != PullPolicy::Always && self.docker.inspect_image(image).await.is_ok() {
+            trace!("image '{image}' found locally, not pulling");
+            return Ok(());
+        }
+        if policy == PullPolicy::Never {
+            return Err(LocalProvisionerError::ImagePullFailed {
+                image: image.to_string(),
+                message:
+                    "the image is not available locally, and the pull policy is set to 'never'"
+                        .to_string(),
+            });
+        }
+
+        self.pull_image(image).await
+    }
+
+    async fn pull_image(&self, image: &str) -> Result<(), LocalProvisionerError> {
         trace!("pulling latest image for '{image}'");
         let mut layers = Vec::new();
 
@@ -293,7 +914,16 @@ impl LocalProvisioner {
         let mut output = self.docker.create_image(create_image_options, None, None);
 
         while let Some(line) = output.next().await {
-            let info = line.expect("failed to create image");
+            let info = line.map_err(|e| match e {
+                bollard::errors::Error::DockerResponseServerError { message, .. }
+                | bollard::errors::Error::DockerStreamError { error: message } => {
+                    LocalProvisionerError::ImagePullFailed {
+                        image: image.to_string(),
+                        message,
+                    }
+                }
+                e => LocalProvisionerError::DaemonUnreachable(e),
+            })?;
 
             if let Some(id) = info.id.as_ref() {
                 match layers
@@ -359,6 +989,268 @@ fn print_layers(layers: &Vec<CreateImageInfo>) {
         .expect("to reset cursor position");
 }
 
+/// A client for the container engine at `docker_host`, or the default one
+pub fn connect_docker(docker_host: Option<&str>) -> Result<Docker, LocalProvisionerError> {
+    // This only constructs the client and does not try to connect.
+    // If the socket is not found, a "no such file" error will happen on the first request to Docker.
+    match docker_host {
+        None => Docker::connect_with_defaults(),
+        Some(host) if host.starts_with("tcp://") || host.starts_with("http://") => {
+            Docker::connect_with_http(host, DOCKER_TIMEOUT_SECS, API_DEFAULT_VERSION)
+        }
+        Some(host) => Docker::connect_with_local(host, DOCKER_TIMEOUT_SECS, API_DEFAULT_VERSION),
+    }
+    .map_err(LocalProvisionerError::DaemonUnreachable)
+}
+
+const DOCKER_TIMEOUT_SECS: u64 = 120;
+/// How long to wait for a database to accept connections
+const READY_TIMEOUT: Duration = Duration::from_secs(120);
+/// How long a single TCP or HTTP readiness check may take
+const READY_CHECK_TIMEOUT: Duration = Duration::from_secs(5);
+/// Label with the name of the project that a container was provisioned for
+const PROJECT_LABEL: &str = "dev.shuttle.project";
+/// Label with the resource type, or the container name for containers requested by resource crates
+const RESOURCE_LABEL: &str = "dev.shuttle.resource";
+
+/// File in the data directory of a database that records that it has been seeded
+const SEED_MARKER: &str = ".shuttle_seeded";
+
+/// The SQL files to seed a database with, in name order if the path is a directory
+fn seed_files(path: &Path) -> Result<Vec<PathBuf>> {
+    if !path.is_dir() {
+        if !path.is_file() {
+            bail!("seed file {} does not exist", path.display());
+        }
+        return Ok(vec![path.to_path_buf()]);
+    }
+
+    let mut files = std::fs::read_dir(path)
+        .with_context(|| format!("reading {}", path.display()))?
+        .map(|entry| entry.map(|e| e.path()))
+        .collect::<std::io::Result<Vec<_>>>()?;
+    files.retain(|f| f.is_file() && f.extension().is_some_and(|e| e == "sql"));
+    files.sort();
+
+    Ok(files)
+}
+
+/// The readiness check to use for a container, falling back to a TCP check on the main port
+/// if the check uses a port that the container does not publish
+fn published_check(
+    check: ReadinessCheck,
+    host_ports: &BTreeMap<String, String>,
+    main_port: &str,
+) -> ReadinessCheck {
+    match check {
+        ReadinessCheck::Tcp { ref port } | ReadinessCheck::Http { ref port, .. }
+            if !host_ports.contains_key(port) =>
+        {
+            ReadinessCheck::Tcp {
+                port: main_port.to_string(),
+            }
+        }
+        check => check,
+    }
+}
+
+fn container_labels(project_name: &str, resource: &str) -> HashMap<String, String> {
+    HashMap::from([
+        (PROJECT_LABEL.to_string(), project_name.to_string()),
+        (RESOURCE_LABEL.to_string(), resource.to_string()),
+    ])
+}
+
+/// A container that was provisioned by `shuttle run`
+#[derive(Debug, Serialize)]
+pub struct LocalContainer {
+    pub name: String,
+    pub resource: String,
+    pub image: String,
+    pub state: String,
+    pub status: String,
+    /// Published ports as `host:container`
+    pub ports: Vec<String>,
+}
+
+impl LocalContainer {
+    /// The container, if it was provisioned for this project.
+    /// Containers created before labels were added are recognised by their name.
+    fn for_project(c: &ContainerSummary, project_name: &str) -> Option<Self> {
+        let name = c
+            .names
+            .iter()
+            .flatten()
+            .next()
+            .map(|n| n.trim_start_matches('/').to_string())
+            .or_else(|| c.id.clone())
+            .unwrap_or_default();
+        let labels = c.labels.as_ref();
+        let resource = match labels.and_then(|l| l.get(PROJECT_LABEL)) {
+            Some(project) if project == project_name => labels
+                .and_then(|l| l.get(RESOURCE_LABEL).cloned())
+                .unwrap_or_default(),
+            Some(_) => return None,
+            None => resource_from_container_name(
+                name.strip_prefix(&format!("shuttle_{project_name}_"))?,
+            ),
+        };
+
+        Some(Self {
+            name,
+            resource,
+            image: c.image.clone().unwrap_or_default(),
+            state: c.state.clone().unwrap_or_default(),
+            status: c.status.clone().unwrap_or_default(),
+            ports: c
+                .ports
+                .iter()
+                .flatten()
+                .filter_map(|p| {
+                    p.public_port
+                        .map(|host| format!("{host}:{}", p.private_port))
+                })
+                .collect(),
+        })
+    }
+}
+
+/// The resource of a container without labels, from the part of its name after the project name
+fn resource_from_container_name(suffix: &str) -> String {
+    DATABASE_TYPES
+        .into_iter()
+        .find(|t| db_type_to_config(t.clone(), "").r#type == suffix)
+        .map_or_else(|| suffix.to_string(), |t| t.to_string())
+}
+
+/// Only keep the containers of `resource`, if given.
+/// Errors with the resources that do have containers if none match, since a typo would otherwise do nothing.
+fn select_resource(
+    containers: Vec<(LocalContainer, ContainerSummary)>,
+    resource: Option<&str>,
+) -> Result<Vec<(LocalContainer, ContainerSummary)>> {
+    let Some(resource) = resource else {
+        return Ok(containers);
+    };
+    let known = containers
+        .iter()
+        .map(|(c, _)| c.resource.clone())
+        .collect::<BTreeSet<_>>();
+    let selected = containers
+        .into_iter()
+        .filter(|(c, _)| c.resource == resource)
+        .collect::<Vec<_>>();
+    if selected.is_empty() {
+        if known.is_empty() {
+            bail!("No local container found for '{resource}'. This project has no local resource containers.");
+        }
+        bail!(
+            "No local container found for '{resource}'. Resources with local containers: {}",
+            known.into_iter().collect::<Vec<_>>().join(", ")
+        );
+    }
+
+    Ok(selected)
+}
+
+pub fn get_local_containers_table(containers: &[LocalContainer], raw: bool) -> String {
+    if containers.is_empty() {
+        return "No local resource containers found for this project\n".to_string();
+    }
+
+    let mut table = Table::new();
+    table
+        .load_preset(if raw { NOTHING } else { UTF8_BORDERS_ONLY })
+        .set_content_arrangement(ContentArrangement::Disabled)
+        .set_header(vec!["Resource", "Container", "Image", "Status", "Ports"]);
+
+    for c in containers {
+        table.add_row(vec![
+            Cell::new(&c.resource).add_attribute(Attribute::Bold),
+            Cell::new(&c.name),
+            Cell::new(&c.image),
+            Cell::new(&c.status).fg(if c.state == "running" {
+                Color::Green
+            } else {
+                Color::DarkGrey
+            }),
+            Cell::new(c.ports.join(", ")),
+        ]);
+    }
+
+    format!("{table}\n")
+}
+
+/// The resource types that are provisioned as local database containers
+const DATABASE_TYPES: [ResourceType; 4] = [
+    ResourceType::DatabaseSharedPostgres,
+    ResourceType::DatabaseAwsRdsPostgres,
+    ResourceType::DatabaseAwsRdsMySql,
+    ResourceType::DatabaseAwsRdsMariaDB,
+];
+
+fn is_database(resource_type: &ResourceType) -> bool {
+    DATABASE_TYPES.contains(resource_type)
+}
+
+/// Command that writes an SQL dump of the database to stdout
+fn dump_cmd(config: &EngineConfig, database_name: &str) -> Vec<String> {
+    match config.engine.as_str() {
+        "postgres" => vec![
+            "pg_dump".to_string(),
+            format!("--username={}", config.username),
+            "--clean".to_string(),
+            "--if-exists".to_string(),
+            "--no-owner".to_string(),
+            database_name.to_string(),
+        ],
+        _ => vec![
+            "mysqldump".to_string(),
+            format!("--user={}", config.username),
+            format!("--password={}", config.password.expose()),
+            "--single-transaction".to_string(),
+            database_name.to_string(),
+        ],
+    }
+}
+
+/// Command that runs the SQL statements from stdin against the database
+fn restore_cmd(config: &EngineConfig, database_name: &str) -> Vec<String> {
+    match config.engine.as_str() {
+        "postgres" => vec![
+            "psql".to_string(),
+            format!("--username={}", config.username),
+            format!("--dbname={database_name}"),
+            "--quiet".to_string(),
+            "--set=ON_ERROR_STOP=1".to_string(),
+        ],
+        _ => vec![
+            "mysql".to_string(),
+            format!("--user={}", config.username),
+            format!("--password={}", config.password.expose()),
+            database_name.to_string(),
+        ],
+    }
+}
+
+/// What a container is created with, if it does not exist yet
+struct NewContainer {
+    image: String,
+    /// Internal ports to publish to the host, each on a free port
+    ports: Vec<String>,
+    env: Option<Vec<String>>,
+    binds: Option<Vec<String>>,
+    cmd: Option<Vec<String>>,
+    labels: HashMap<String, String>,
+}
+
+/// Name of the named volume configured for a database container, if any
+fn volume_name(container_name: &str, config: Option<&LocalResourceConfig>) -> Option<String> {
+    config
+        .and_then(|c| c.volume.as_ref())
+        .and_then(|v| v.name(&format!("{container_name}_data")))
+}
+
 struct EngineConfig {
     r#type: String,
     image: String,
@@ -368,6 +1260,8 @@ struct EngineConfig {
     port: String,
     env: Option<Vec<String>>,
     is_ready_cmd: Vec<String>,
+    /// Where the engine keeps its data inside the container
+    data_dir: String,
 }
 
 fn db_type_to_config(db_type: ResourceType, database_name: &str) -> EngineConfig {
@@ -388,6 +1282,7 @@ fn db_type_to_config(db_type: ResourceType, database_name: &str) -> EngineConfig
                 "-c".to_string(),
                 "pg_isready | grep 'accepting connections'".to_string(),
             ],
+            data_dir: "/var/lib/postgresql/data".to_string(),
         },
         ResourceType::DatabaseAwsRdsPostgres => EngineConfig {
             r#type: "aws_rds_postgres".to_string(),
@@ -405,6 +1300,7 @@ fn db_type_to_config(db_type: ResourceType, database_name: &str) -> EngineConfig
                 "-c".to_string(),
                 "pg_isready | grep 'accepting connections'".to_string(),
             ],
+            data_dir: "/var/lib/postgresql/data".to_string(),
         },
         ResourceType::DatabaseAwsRdsMariaDB => EngineConfig {
             r#type: "aws_rds_mariadb".to_string(),
@@ -424,6 +1320,7 @@ fn db_type_to_config(db_type: ResourceType, database_name: &str) -> EngineConfig
                 "-e".to_string(),
                 "show databases;".to_string(),
             ],
+            data_dir: "/var/lib/mysql".to_string(),
         },
         ResourceType::DatabaseAwsRdsMySql => EngineConfig {
             r#type: "aws_rds_mysql".to_string(),
@@ -443,15 +1340,21 @@ fn db_type_to_config(db_type: ResourceType, database_name: &str) -> EngineConfig
                 "-e".to_string(),
                 "show databases;".to_string(),
             ],
+            data_dir: "/var/lib/mysql".to_string(),
         },
         _ => panic!("Non-database resource type provided: {db_type}"),
     }
 }
 
-#[derive(Clone)]
 pub struct ProvApiState {
     pub project_name: String,
     pub secrets: HashMap<String, String>,
+    /// The `[local]` section of Shuttle.toml
+    pub local: ProjectLocalConfig,
+    /// Seed paths are relative to this
+    pub project_directory: PathBuf,
+    /// Serializes provisioning, so that services that start at the same time don't race to create the same container
+    pub provision_lock: tokio::sync::Mutex<()>,
 }
 
 pub struct ProvisionerServer;
@@ -495,8 +1398,17 @@ pub async fn handler(
             .status(200)
             .body(BoxBody::new(Full::new(Bytes::from(bytes)))),
         Err(e) => {
-            eprintln!("Encountered error when provisioning: {e}");
-            Response::builder().status(500).body(Empty::new().boxed())
+            let mut message = format!("{e:#}");
+            if let Some(hint) = e.downcast_ref::<LocalProvisionerError>().map(|e| e.hint()) {
+                message.push_str(&format!("\nHint: {hint}"));
+            }
+            eprintln!("Encountered error when provisioning: {message}");
+            let error = ApiError::new(message, StatusCode::INTERNAL_SERVER_ERROR);
+            Response::builder()
+                .status(StatusCode::INTERNAL_SERVER_ERROR)
+                .body(BoxBody::new(Full::new(Bytes::from(
+                    serde_json::to_vec(&error).unwrap(),
+                ))))
         }
     }
 }
@@ -520,7 +1432,9 @@ async fn provision(
             serde_json::to_vec(&response).unwrap()
         }
         (Method::POST, "/projects/proj_LOCAL/resources") => {
-            let prov = LocalProvisioner::new().unwrap();
+            let _guard = state.provision_lock.lock().await;
+            let prov = LocalProvisioner::new(state.local.clone())
+                .context("connecting to the container engine")?;
             let shuttle_resource: ProvisionResourceRequest =
                 serde_json::from_slice(&body).context("deserializing resource request")?;
 
@@ -531,13 +1445,25 @@ async fn provision(
                 | ResourceType::DatabaseAwsRdsPostgres => {
                     let config: DbInput = serde_json::from_value(shuttle_resource.config.clone())
                         .context("deserializing resource config")?;
-                    let res = prov.get_db_connection_string(
+                    let seed = config
+                        .seed
+                        .map(PathBuf::from)
+                        .or_else(|| {
+                            state
+                                .local
+                                .resource(shuttle_resource.r#type.as_ref())
+                                .and_then(|r| r.seed.clone())
+                        })
+                        .map(|p| state.project_directory.join(p));
+                    let res = prov
+                        .get_db_connection_string(
                             &state.project_name,
                             shuttle_resource.r#type.clone(),
                             config.db_name,
+                            seed,
                         )
                         .await
-                        .context("Failed to start database container. Make sure that a Docker engine is running.")?;
+                        .context("Failed to start database container")?;
                     ResourceResponse {
                         r#type: shuttle_resource.r#type,
                         state: resource::ResourceState::Ready,
@@ -548,9 +1474,10 @@ async fn provision(
                 ResourceType::Container => {
                     let config = serde_json::from_value(shuttle_resource.config.clone())
                         .context("deserializing resource config")?;
-                    let res = prov.start_container(config)
-                            .await
-                            .context("Failed to start Docker container. Make sure that a Docker engine is running.")?;
+                    let res = prov
+                        .start_container(config)
+                        .await
+                        .context("Failed to start container")?;
                     ResourceResponse {
                         r#type: shuttle_resource.r#type,
                         state: resource::ResourceState::Ready,
@@ -575,3 +1502,229 @@ async fn provision(
         _ => bail!("Received unsupported resource request"),
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn container_request_defaults() {
+        // requests from resource crates that only know about the original fields
+        let req: ContainerRequest = serde_json::from_value(serde_json::json!({
+            "project_name": "app",
+            "container_name": "qdrant",
+            "image": "qdrant/qdrant",
+            "port": "6334/tcp",
+            "env": [],
+        }))
+        .unwrap();
+        assert!(req.extra_ports.is_empty());
+        assert!(req.readiness.is_none());
+
+        let check: ReadinessCheck = serde_json::from_value(serde_json::json!({
+            "type": "http",
+            "port": "6333/tcp",
+            "path": "/readyz",
+        }))
+        .unwrap();
+        assert!(matches!(check, ReadinessCheck::Http { .. }));
+    }
+
+    fn summary(name: &str, labels: &[(&str, &str)]) -> ContainerSummary {
+        ContainerSummary {
+            names: Some(vec![format!("/{name}")]),
+            labels: Some(
+                labels
+                    .iter()
+                    .map(|(k, v)| (k.to_string(), v.to_string()))
+                    .collect(),
+            ),
+            state: Some("running".to_string()),
+            ports: Some(vec![bollard::models::Port {
+                private_port: 5432,
+                public_port: Some(40000),
+                ..Default::default()
+            }]),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn local_containers_of_project() {
+        let labelled = summary(
+            "shuttle_app_shared_postgres",
+            &[
+                (PROJECT_LABEL, "app"),
+                (RESOURCE_LABEL, "database::shared::postgres"),
+            ],
+        );
+        let c = LocalContainer::for_project(&labelled, "app").unwrap();
+        assert_eq!(c.name, "shuttle_app_shared_postgres");
+        assert_eq!(c.resource, "database::shared::postgres");
+        assert_eq!(c.ports, vec!["40000:5432"]);
+        assert!(LocalContainer::for_project(&labelled, "other").is_none());
+
+        // created before labels were added
+        let unlabelled = summary("shuttle_app_aws_rds_mysql", &[]);
+        let c = LocalContainer::for_project(&unlabelled, "app").unwrap();
+        assert_eq!(c.resource, "database::aws_rds::mysql");
+        let unlabelled = summary("shuttle_app_qdrant", &[]);
+        let c = LocalContainer::for_project(&unlabelled, "app").unwrap();
+        assert_eq!(c.resource, "qdrant");
+        assert!(LocalContainer::for_project(&unlabelled, "other").is_none());
+        assert!(LocalContainer::for_project(&summary("postgres", &[]), "app").is_none());
+
+        // the name of a labelled container does not matter
+        let other_project = summary("shuttle_app_qdrant", &[(PROJECT_LABEL, "app2")]);
+        assert!(LocalContainer::for_project(&other_project, "app").is_none());
+    }
+
+    #[test]
+    fn select_resource_lists_known_resources() {
+        let containers = || {
+            ["shuttle_app_shared_postgres", "shuttle_app_qdrant"]
+                .into_iter()
+                .map(|n| {
+                    let c = summary(n, &[]);
+                    (LocalContainer::for_project(&c, "app").unwrap(), c)
+                })
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(select_resource(containers(), None).unwrap().len(), 2);
+        let selected = select_resource(containers(), Some("qdrant")).unwrap();
+        assert_eq!(selected.len(), 1);
+        assert_eq!(selected[0].0.name, "shuttle_app_qdrant");
+
+        let err = select_resource(containers(), Some("postgres")).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "No local container found for 'postgres'. \
+            Resources with local containers: database::shared::postgres, qdrant"
+        );
+        assert!(select_resource(Vec::new(), Some("postgres")).is_err());
+    }
+
+    #[test]
+    fn readiness_check_falls_back_to_main_port() {
+        let host_ports = BTreeMap::from([("6334/tcp".to_string(), "40000".to_string())]);
+        let http = || ReadinessCheck::Http {
+            port: "6333/tcp".to_string(),
+            path: "/readyz".to_string(),
+        };
+
+        // an existing container that does not publish the port of the check
+        assert!(matches!(
+            published_check(http(), &host_ports, "6334/tcp"),
+            ReadinessCheck::Tcp { port } if port == "6334/tcp"
+        ));
+
+        let host_ports = BTreeMap::from([
+            ("6333/tcp".to_string(), "40001".to_string()),
+            ("6334/tcp".to_string(), "40000".to_string()),
+        ]);
+        assert!(matches!(
+            published_check(http(), &host_ports, "6334/tcp"),
+            ReadinessCheck::Http { port, .. } if port == "6333/tcp"
+        ));
+        assert!(matches!(
+            published_check(
+                ReadinessCheck::Exec {
+                    command: vec!["true".to_string()]
+                },
+                &host_ports,
+                "6334/tcp"
+            ),
+            ReadinessCheck::Exec { .. }
+        ));
+    }
+
+    #[test]
+    fn classifies_docker_errors() {
+        let error = |message: &str| bollard::errors::Error::DockerResponseServerError {
+            status_code: 500,
+            message: message.to_string(),
+        };
+
+        assert!(matches!(
+            LocalProvisionerError::docker(
+                "db",
+                error("Bind for 0.0.0.0:5432 failed: port is already allocated")
+            ),
+            LocalProvisionerError::PortConflict { .. }
+        ));
+        assert!(matches!(
+            LocalProvisionerError::docker("db", error("container is marked for removal")),
+            LocalProvisionerError::UnexpectedState { .. }
+        ));
+        assert!(matches!(
+            LocalProvisionerError::docker(
+                "db",
+                bollard::errors::Error::SocketNotFoundError("/var/run/docker.sock".to_string())
+            ),
+            LocalProvisionerError::DaemonUnreachable(_)
+        ));
+
+        // the hint is found through added context
+        let e = anyhow::Error::from(LocalProvisionerError::ReadinessTimeout {
+            container: "db".to_string(),
+            timeout: READY_TIMEOUT,
+        })
+        .context("Failed to start database container");
+        assert!(e
+            .downcast_ref::<LocalProvisionerError>()
+            .is_some_and(|e| e.hint().contains("docker logs db")));
+    }
+
+    #[test]
+    fn finds_seed_files() {
+        let dir = tempfile::tempdir().unwrap();
+        for name in ["02_data.sql", "01_schema.sql", "README.md"] {
+            std::fs::write(dir.path().join(name), "").unwrap();
+        }
+
+        assert_eq!(
+            seed_files(dir.path()).unwrap(),
+            vec![
+                dir.path().join("01_schema.sql"),
+                dir.path().join("02_data.sql")
+            ]
+        );
+        let file = dir.path().join("02_data.sql");
+        assert_eq!(seed_files(&file).unwrap(), vec![file]);
+        assert!(seed_files(&dir.path().join("missing.sql")).is_err());
+    }
+
+    #[test]
+    fn dump_and_restore_commands() {
+        let postgres = db_type_to_config(ResourceType::DatabaseSharedPostgres, "app");
+        assert_eq!(
+            dump_cmd(&postgres, "app"),
+            vec![
+                "pg_dump",
+                "--username=postgres",
+                "--clean",
+                "--if-exists",
+                "--no-owner",
+                "app"
+            ]
+        );
+        assert_eq!(restore_cmd(&postgres, "app")[0], "psql");
+
+        let mysql = db_type_to_config(ResourceType::DatabaseAwsRdsMySql, "app");
+        assert_eq!(
+            dump_cmd(&mysql, "app"),
+            vec![
+                "mysqldump",
+                "--user=root",
+                "--password=mysql",
+                "--single-transaction",
+                "app"
+            ]
+        );
+        assert_eq!(
+            restore_cmd(&mysql, "app"),
+            vec!["mysql", "--user=root", "--password=mysql", "app"]
+        );
+    }
+}
@@ -0,0 +1,261 @@
+use std::{
+    collections::HashMap,
+    io::Write,
+    path::PathBuf,
+    process::{Command, Stdio},
+};
+
+use anyhow::{bail, Context, Result};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use serde::Deserialize;
+use tracing::{debug, trace};
+
+/// The key that Docker uses for Docker Hub in its config file and credential helpers
+const DOCKER_HUB_SERVER: &str = "https://index.docker.io/v1/";
+
+/// The parts of `~/.docker/config.json` that are relevant for finding registry credentials
+#[derive(Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct DockerConfig {
+    #[serde(default)]
+    auths: HashMap<String, DockerConfigAuth>,
+    /// Credential helper used for all registries
+    creds_store: Option<String>,
+    /// Credential helpers used for specific registries
+    #[serde(default)]
+    cred_helpers: HashMap<String, String>,
+}
+
+#[derive(Deserialize)]
+struct DockerConfigAuth {
+    /// base64 encoded `username:password`
+    auth: Option<String>,
+    username: Option<String>,
+    password: Option<String>,
+}
+
+/// Output of `docker-credential-<helper> get`
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct CredentialHelperOutput {
+    username: String,
+    secret: String,
+}
+
+/// The registry that an image reference points to, as Docker would resolve it
+pub fn registry_of(image: &str) -> &str {
+    match image.split_once('/') {
+        // The first component is only a registry if it looks like a host name
+        Some((first, _)) if first.contains(['.', ':']) || first == "localhost" => first,
+        _ => "docker.io",
+    }
+}
+
+/// Strip the scheme and path from a registry key in the Docker config,
+/// and map the Docker Hub aliases to a single name
+fn normalize_registry(key: &str) -> &str {
+    let key = key
+        .strip_prefix("https://")
+        .or_else(|| key.strip_prefix("http://"))
+        .unwrap_or(key);
+    let host = key.split('/').next().unwrap_or(key);
+
+    match host {
+        "index.docker.io" | "registry-1.docker.io" => "docker.io",
+        host => host,
+    }
+}
+
+fn docker_config_path() -> Option<PathBuf> {
+    match std::env::var_os("DOCKER_CONFIG") {
+        Some(dir) => Some(PathBuf::from(dir).join("config.json")),
+        None => dirs::home_dir().map(|home| home.join(".docker").join("config.json")),
+    }
+}
+
+/// Look up the username and password for the registry of `image` in the Docker config file,
+/// using the configured credential helper if there is one.
+///
+/// Returns None if there is no config file or it has no credentials for the registry.
+pub fn docker_config_credentials(image: &str) -> Result<Option<(String, String)>> {
+    let Some(path) = docker_config_path().filter(|p| p.exists()) else {
+        trace!("no docker config file found");
+        return Ok(None);
+    };
+    let config: DockerConfig = serde_json::from_str(
+        &std::fs::read_to_string(&path)
+            .with_context(|| format!("reading docker config at {}", path.display()))?,
+    )
+    .with_context(|| format!("parsing docker config at {}", path.display()))?;
+
+    credentials_from_config(&config, registry_of(image))
+}
+
+fn credentials_from_config(
+    config: &DockerConfig,
+    registry: &str,
+) -> Result<Option<(String, String)>> {
+    let server = if registry == "docker.io" {
+        DOCKER_HUB_SERVER
+    } else {
+        registry
+    };
+
+    // A helper for this specific registry takes precedence over everything else
+    if let Some(helper) = config
+        .cred_helpers
+        .iter()
+        .find(|(k, _)| normalize_registry(k) == registry)
+        .map(|(_, helper)| helper)
+    {
+        debug!(helper, registry, "using docker credential helper");
+        return credential_helper_get(helper, server);
+    }
+
+    if let Some(creds) = auths_credentials(config, registry)? {
+        return Ok(Some(creds));
+    }
+
+    // Entries in `auths` are left empty when the credentials are kept in the global helper
+    match config.creds_store.as_ref() {
+        Some(helper) => {
+            debug!(helper, registry, "using docker credential store");
+            credential_helper_get(helper, server)
+        }
+        None => Ok(None),
+    }
+}
+
+/// Credentials stored directly in the `auths` section of the config
+fn auths_credentials(config: &DockerConfig, registry: &str) -> Result<Option<(String, String)>> {
+    let Some(auth) = config
+        .auths
+        .iter()
+        .find(|(k, _)| normalize_registry(k) == registry)
+        .map(|(_, auth)| auth)
+    else {
+        return Ok(None);
+    };
+
+    if let (Some(username), Some(password)) = (&auth.username, &auth.password) {
+        return Ok(Some((username.clone(), password.clone())));
+    }
+    let Some(encoded) = &auth.auth else {
+        return Ok(None);
+    };
+    let decoded = String::from_utf8(
+        STANDARD
+            .decode(encoded)
+            .context("decoding auth in docker config")?,
+    )
+    .context("auth in docker config is not valid utf-8")?;
+    let Some((username, password)) = decoded.split_once(':') else {
+        bail!("auth for {registry} in docker config is not in the form username:password");
+    };
+
+    Ok(Some((username.to_owned(), password.to_owned())))
+}
+
+fn credential_helper_get(helper: &str, server: &str) -> Result<Option<(String, String)>> {
+    let program = format!("docker-credential-{helper}");
+    let mut child = match Command::new(&program)
+        .arg("get")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+    {
+        Ok(child) => child,
+        // Common when a docker config is copied to a machine without the helper, such as a CI runner
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            eprintln!(
+                "WARNING: The docker credential helper {program} from the docker config was not found. \
+                Continuing without registry credentials."
+            );
+            return Ok(None);
+        }
+        Err(e) => return Err(e).with_context(|| format!("running {program}")),
+    };
+    child
+        .stdin
+        .take()
+        .expect("stdin to be piped")
+        .write_all(server.as_bytes())
+        .with_context(|| format!("writing to {program}"))?;
+    let output = child
+        .wait_with_output()
+        .with_context(|| format!("waiting for {program}"))?;
+
+    if !output.status.success() {
+        // The helpers exit with an error when they have no credentials for the server
+        debug!(
+            stderr = %String::from_utf8_lossy(&output.stderr),
+            "{program} found no credentials"
+        );
+        return Ok(None);
+    }
+    let creds: CredentialHelperOutput = serde_json::from_slice(&output.stdout)
+        .with_context(|| format!("parsing output of {program}"))?;
+
+    Ok(Some((creds.username, creds.secret)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_registry() {
+        assert_eq!(registry_of("nginx"), "docker.io");
+        assert_eq!(registry_of("library/nginx:latest"), "docker.io");
+        assert_eq!(registry_of("ghcr.io/org/app:v1"), "ghcr.io");
+        assert_eq!(registry_of("localhost:5000/app"), "localhost:5000");
+        assert_eq!(registry_of("localhost/app"), "localhost");
+    }
+
+    #[test]
+    fn reads_auths() {
+        let config: DockerConfig = serde_json::from_str(
+            r#"{
+                "auths": {
+                    "https://index.docker.io/v1/": { "auth": "dXNlcjpwYXNzOndvcmQ=" },
+                    "registry.example.com": { "username": "a", "password": "b" }
+                }
+            }"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            credentials_from_config(&config, "docker.io").unwrap(),
+            Some(("user".to_owned(), "pass:word".to_owned()))
+        );
+        assert_eq!(
+            credentials_from_config(&config, "registry.example.com").unwrap(),
+            Some(("a".to_owned(), "b".to_owned()))
+        );
+        assert_eq!(credentials_from_config(&config, "ghcr.io").unwrap(), None);
+    }
+
+    #[test]
+    fn missing_credential_helper() {
+        let config: DockerConfig = serde_json::from_str(
+            r#"{
+                "auths": {
+                    "registry.example.com": { "username": "a", "password": "b" },
+                    "ghcr.io": {}
+                },
+                "credsStore": "shuttle-test-does-not-exist"
+            }"#,
+        )
+        .unwrap();
+
+        // credentials in auths are used without running the helper
+        assert_eq!(
+            credentials_from_config(&config, "registry.example.com").unwrap(),
+            Some(("a".to_owned(), "b".to_owned()))
+        );
+        // the helper is not installed
+        assert_eq!(credentials_from_config(&config, "ghcr.io").unwrap(), None);
+        assert_eq!(credentials_from_config(&config, "docker.io").unwrap(), None);
+    }
+}
@@ -0,0 +1,411 @@
+use std::{collections::HashMap, path::Path};
+
+use anyhow::{anyhow, bail, Context, Result};
+use toml_edit::{ImDocument, Item, Value};
+
+/// File formats that secrets can be loaded from
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SecretsFormat {
+    Toml,
+    Env,
+    Json,
+    Yaml,
+}
+
+impl SecretsFormat {
+    /// Detect the format from the file name, defaulting to TOML
+    pub fn from_path(path: &Path) -> Self {
+        let file_name = path
+            .file_name()
+            .map(|n| n.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+
+        // .env, .env.local, prod.env, ...
+        if file_name == ".env" || file_name.starts_with(".env.") || file_name.ends_with(".env") {
+            return Self::Env;
+        }
+
+        match path
+            .extension()
+            .map(|e| e.to_string_lossy().to_lowercase())
+            .as_deref()
+        {
+            Some("json") => Self::Json,
+            Some("yaml" | "yml") => Self::Yaml,
+            _ => Self::Toml,
+        }
+    }
+}
+
+/// Parse the contents of a secrets file into a flat map of secrets.
+///
+/// Nested tables are flattened into dotted keys, and numbers and booleans are turned into strings.
+pub fn parse_secrets_file(path: &Path, contents: &str) -> Result<HashMap<String, String>> {
+    let format = SecretsFormat::from_path(path);
+
+    parse_secrets(format, contents)
+        .with_context(|| format!("parsing secrets file {}", path.display()))
+}
+
+pub fn parse_secrets(format: SecretsFormat, contents: &str) -> Result<HashMap<String, String>> {
+    let mut secrets = Secrets {
+        source: contents,
+        format,
+        secrets: HashMap::new(),
+    };
+
+    match format {
+        SecretsFormat::Toml => {
+            let doc = ImDocument::parse(contents).map_err(|e| anyhow!("{e}"))?;
+            let table = doc.as_table();
+            let entries =
+                secrets.toml_entries(table.iter().filter_map(|(k, _)| table.get_key_value(k)));
+            secrets.flatten_toml_table(entries, "")?;
+        }
+        SecretsFormat::Env => {
+            for item in dotenvy::from_read_iter(contents.as_bytes()) {
+                let (key, value) = item.map_err(|e| match e {
+                    dotenvy::Error::LineParse(line, _) => {
+                        let line_number = contents
+                            .lines()
+                            // blank lines would match any line with `starts_with`
+                            .position(|l| {
+                                !l.trim().is_empty() && (l == line || line.starts_with(l))
+                            })
+                            .map(|i| i + 1);
+                        match line_number {
+                            Some(n) => anyhow!("invalid line {n}: '{line}'"),
+                            None => anyhow!("invalid line: '{line}'"),
+                        }
+                    }
+                    e => anyhow!(e),
+                })?;
+                secrets.insert(key, value, None)?;
+            }
+        }
+        SecretsFormat::Json => {
+            let value = serde_json::from_str::<serde_json::Value>(contents)?;
+            let serde_json::Value::Object(map) = value else {
+                bail!("expected a JSON object at the top level");
+            };
+            secrets.flatten_json(map, "")?;
+        }
+        SecretsFormat::Yaml => {
+            let mut docs = yaml_rust2::YamlLoader::load_from_str(contents)?;
+            if docs.len() > 1 {
+                bail!("expected a single YAML document");
+            }
+            match docs.pop() {
+                // empty file
+                None => {}
+                Some(yaml_rust2::Yaml::Hash(map)) => secrets.flatten_yaml(map, "")?,
+                Some(_) => bail!("expected a YAML mapping at the top level"),
+            }
+        }
+    }
+
+    Ok(secrets.secrets)
+}
+
+struct Secrets<'a> {
+    source: &'a str,
+    format: SecretsFormat,
+    secrets: HashMap<String, String>,
+}
+
+impl Secrets<'_> {
+    fn insert(&mut self, key: String, value: String, line: Option<usize>) -> Result<()> {
+        let line = line.or_else(|| self.line_of_key(&key));
+        if self.secrets.contains_key(&key) {
+            bail!("duplicate secret `{key}`{}", at_line(line));
+        }
+        self.secrets.insert(key, value);
+
+        Ok(())
+    }
+
+    fn unsupported(&self, key: &str, kind: &str, line: Option<usize>) -> anyhow::Error {
+        let line = line.or_else(|| self.line_of_key(key));
+        anyhow!(
+            "secret `{key}`{} has an unsupported value ({kind}), expected a string, number, boolean or table",
+            at_line(line)
+        )
+    }
+
+    /// Best-effort lookup of the line a key is defined on, for formats whose parsers don't keep spans
+    fn line_of_key(&self, key: &str) -> Option<usize> {
+        let leaf = key.rsplit('.').next().unwrap_or(key);
+        let needles: Vec<String> = match self.format {
+            SecretsFormat::Toml => return None,
+            SecretsFormat::Env => vec![format!("{leaf}="), format!("{leaf} =")],
+            SecretsFormat::Json => vec![format!("\"{leaf}\"")],
+            SecretsFormat::Yaml => vec![
+                format!("{leaf}:"),
+                format!("\"{leaf}\":"),
+                format!("'{leaf}':"),
+            ],
+        };
+
+        self.source
+            .lines()
+            .position(|line| {
+                let line = line.trim_start();
+                let line = line.strip_prefix("export ").unwrap_or(line);
+                needles.iter().any(|n| line.starts_with(n.as_str()))
+            })
+            .map(|i| i + 1)
+    }
+
+    fn line_of_offset(&self, offset: usize) -> usize {
+        self.source[..offset.min(self.source.len())]
+            .matches('\n')
+            .count()
+            + 1
+    }
+
+    /// Entries of a TOML table, with the line each key is defined on
+    fn toml_entries<'i>(
+        &self,
+        keys: impl Iterator<Item = (&'i toml_edit::Key, &'i Item)>,
+    ) -> Vec<(&'i str, &'i Item, Option<usize>)> {
+        keys.map(|(k, item)| {
+            let line = k.span().map(|s| self.line_of_offset(s.start));
+            (k.get(), item, line)
+        })
+        .collect()
+    }
+
+    fn flatten_toml_table(
+        &mut self,
+        entries: Vec<(&str, &Item, Option<usize>)>,
+        prefix: &str,
+    ) -> Result<()> {
+        for (k, item, line) in entries {
+            let key = format!("{prefix}{k}");
+
+            match item {
+                Item::None => {}
+                Item::Table(t) => {
+                    let entries =
+                        self.toml_entries(t.iter().filter_map(|(k, _)| t.get_key_value(k)));
+                    self.flatten_toml_table(entries, &format!("{key}."))?;
+                }
+                Item::ArrayOfTables(_) => return Err(self.unsupported(&key, "array", line)),
+                Item::Value(Value::InlineTable(t)) => {
+                    let entries =
+                        self.toml_entries(t.iter().filter_map(|(k, _)| t.get_key_value(k)));
+                    self.flatten_toml_table(entries, &format!("{key}."))?;
+                }
+                Item::Value(v) => {
+                    let value = match v {
+                        Value::String(s) => s.value().to_owned(),
+                        Value::Integer(i) => i.value().to_string(),
+                        Value::Float(f) => format!("{:?}", f.value()),
+                        Value::Boolean(b) => b.value().to_string(),
+                        Value::Datetime(d) => d.value().to_string(),
+                        Value::Array(_) => return Err(self.unsupported(&key, "array", line)),
+                        Value::InlineTable(_) => unreachable!("handled above"),
+                    };
+                    self.insert(key, value, line)?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn flatten_json(
+        &mut self,
+        map: serde_json::Map<String, serde_json::Value>,
+        prefix: &str,
+    ) -> Result<()> {
+        use serde_json::Value;
+
+        for (k, v) in map {
+            let key = format!("{prefix}{k}");
+            let value = match v {
+                Value::Object(map) => {
+                    self.flatten_json(map, &format!("{key}."))?;
+                    continue;
+                }
+                Value::String(s) => s,
+                Value::Number(n) => n.to_string(),
+                Value::Bool(b) => b.to_string(),
+                Value::Array(_) => return Err(self.unsupported(&key, "array", None)),
+                Value::Null => return Err(self.unsupported(&key, "null", None)),
+            };
+            self.insert(key, value, None)?;
+        }
+
+        Ok(())
+    }
+
+    fn flatten_yaml(&mut self, map: yaml_rust2::yaml::Hash, prefix: &str) -> Result<()> {
+        use yaml_rust2::Yaml;
+
+        for (k, v) in map {
+            let k = match k {
+                Yaml::String(s) | Yaml::Real(s) => s,
+                Yaml::Integer(i) => i.to_string(),
+                Yaml::Boolean(b) => b.to_string(),
+                _ => bail!("secret keys under `{prefix}` must be strings"),
+            };
+            let key = format!("{prefix}{k}");
+            let value = match v {
+                Yaml::Hash(map) => {
+                    self.flatten_yaml(map, &format!("{key}."))?;
+                    continue;
+                }
+                Yaml::String(s) | Yaml::Real(s) => s,
+                Yaml::Integer(i) => i.to_string(),
+                Yaml::Boolean(b) => b.to_string(),
+                Yaml::Array(_) => return Err(self.unsupported(&key, "sequence", None)),
+                Yaml::Null => return Err(self.unsupported(&key, "null", None)),
+                Yaml::Alias(_) | Yaml::BadValue => {
+                    return Err(self.unsupported(&key, "unresolved alias", None))
+                }
+            };
+            self.insert(key, value, None)?;
+        }
+
+        Ok(())
+    }
+}
+
+fn at_line(line: Option<usize>) -> String {
+    line.map(|l| format!(" on line {l}")).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn map(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn detects_format() {
+        for (path, format) in [
+            ("Secrets.toml", SecretsFormat::Toml),
+            ("Secrets.dev.toml", SecretsFormat::Toml),
+            ("secrets", SecretsFormat::Toml),
+            (".env", SecretsFormat::Env),
+            (".env.local", SecretsFormat::Env),
+            ("prod.env", SecretsFormat::Env),
+            ("secrets.json", SecretsFormat::Json),
+            ("secrets.yaml", SecretsFormat::Yaml),
+            ("secrets.YML", SecretsFormat::Yaml),
+        ] {
+            assert_eq!(SecretsFormat::from_path(Path::new(path)), format, "{path}");
+        }
+    }
+
+    #[test]
+    fn flattens_toml() {
+        let secrets = parse_secrets(
+            SecretsFormat::Toml,
+            r#"
+KEY = "value"
+PORT = 8080
+RATIO = 0.5
+ENABLED = true
+inline = { a = "b" }
+
+[db]
+url = "postgres://localhost"
+
+[db.pool]
+size = 5
+"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            secrets,
+            map(&[
+                ("KEY", "value"),
+                ("PORT", "8080"),
+                ("RATIO", "0.5"),
+                ("ENABLED", "true"),
+                ("inline.a", "b"),
+                ("db.url", "postgres://localhost"),
+                ("db.pool.size", "5"),
+            ])
+        );
+    }
+
+    #[test]
+    fn toml_error_names_key_and_line() {
+        let err = parse_secrets(SecretsFormat::Toml, "A = 'a'\n\n[nested]\nLIST = [1, 2]\n")
+            .unwrap_err()
+            .to_string();
+
+        assert!(err.contains("`nested.LIST`"), "{err}");
+        assert!(err.contains("line 4"), "{err}");
+    }
+
+    #[test]
+    fn parses_env() {
+        let secrets = parse_secrets(
+            SecretsFormat::Env,
+            "# comment\nKEY=value\nexport QUOTED=\"with spaces\"\n",
+        )
+        .unwrap();
+
+        assert_eq!(secrets, map(&[("KEY", "value"), ("QUOTED", "with spaces")]));
+    }
+
+    #[test]
+    fn env_error_names_line() {
+        let err = parse_secrets(SecretsFormat::Env, "KEY=value\n\nBAD LINE\n")
+            .unwrap_err()
+            .to_string();
+
+        assert!(err.contains("line 3"), "{err}");
+    }
+
+    #[test]
+    fn parses_json_and_yaml() {
+        let expected = map(&[("KEY", "value"), ("db.port", "5432"), ("db.tls", "false")]);
+
+        let json = parse_secrets(
+            SecretsFormat::Json,
+            r#"{"KEY": "value", "db": {"port": 5432, "tls": false}}"#,
+        )
+        .unwrap();
+        assert_eq!(json, expected);
+
+        let yaml = parse_secrets(
+            SecretsFormat::Yaml,
+            "KEY: value\ndb:\n  port: 5432\n  tls: false\n",
+        )
+        .unwrap();
+        assert_eq!(yaml, expected);
+    }
+
+    #[test]
+    fn yaml_error_names_key_and_line() {
+        let err = parse_secrets(SecretsFormat::Yaml, "KEY: value\nLIST:\n  - 1\n")
+            .unwrap_err()
+            .to_string();
+
+        assert!(err.contains("`LIST`"), "{err}");
+        assert!(err.contains("line 2"), "{err}");
+    }
+
+    #[test]
+    fn rejects_duplicate_flattened_keys() {
+        let err = parse_secrets(
+            SecretsFormat::Json,
+            r#"{"db.url": "a", "db": {"url": "b"}}"#,
+        )
+        .unwrap_err()
+        .to_string();
+
+        assert!(err.contains("duplicate secret `db.url`"), "{err}");
+    }
+}
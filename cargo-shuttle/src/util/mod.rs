@@ -1,4 +1,5 @@
 pub mod bacon;
+pub mod watch;
 
 use std::{
     fmt::Write,
@@ -0,0 +1,135 @@
+use std::{
+    collections::BTreeMap,
+    path::PathBuf,
+    time::{Duration, SystemTime},
+};
+
+use anyhow::{Context, Result};
+use tokio::sync::mpsc;
+use tracing::{debug, trace, warn};
+
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Modification time and size of a file at the time of a snapshot
+type FileStamp = (Option<SystemTime>, u64);
+
+/// Watches the files that would be included in a deployment archive, and sends
+/// the list of changed paths whenever one is added, removed or modified.
+///
+/// Polls the file tree instead of using OS file events, so that the exact same
+/// ignore rules as [`crate::archive::gather_build_files`] are applied.
+/// See [`crate::archive::list_build_files`] for which files are watched.
+pub fn watch_files(
+    project_directory: PathBuf,
+    include_patterns: Option<Vec<String>>,
+) -> Result<mpsc::Receiver<Vec<PathBuf>>> {
+    let mut previous = snapshot(&project_directory, include_patterns.as_ref())
+        .context("taking initial snapshot of project files")?;
+    let (tx, rx) = mpsc::channel(8);
+
+    tokio::spawn(async move {
+        loop {
+            tokio::time::sleep(POLL_INTERVAL).await;
+
+            let current = {
+                let project_directory = project_directory.clone();
+                let include_patterns = include_patterns.clone();
+                match tokio::task::spawn_blocking(move || {
+                    snapshot(&project_directory, include_patterns.as_ref())
+                })
+                .await
+                {
+                    Ok(Ok(s)) => s,
+                    Ok(Err(e)) => {
+                        // files can disappear mid-walk, so just try again on the next tick
+                        warn!(error = %e, "failed to list project files");
+                        continue;
+                    }
+                    Err(e) => {
+                        warn!(error = %e, "file watcher task panicked");
+                        break;
+                    }
+                }
+            };
+
+            let changed = diff(&previous, &current);
+            previous = current;
+            if changed.is_empty() {
+                continue;
+            }
+
+            debug!(?changed, "detected file changes");
+            if tx.send(changed).await.is_err() {
+                trace!("file change receiver dropped, stopping watcher");
+                break;
+            }
+        }
+    });
+
+    Ok(rx)
+}
+
+fn snapshot(
+    project_directory: &std::path::Path,
+    include_patterns: Option<&Vec<String>>,
+) -> Result<BTreeMap<PathBuf, FileStamp>> {
+    let files = crate::archive::list_build_files(project_directory, include_patterns)?;
+
+    Ok(files
+        .into_iter()
+        .filter_map(|(path, name)| {
+            let meta = std::fs::metadata(&path).ok()?;
+            Some((name, (meta.modified().ok(), meta.len())))
+        })
+        .collect())
+}
+
+/// Paths that were added, removed or modified between two snapshots
+fn diff(
+    previous: &BTreeMap<PathBuf, FileStamp>,
+    current: &BTreeMap<PathBuf, FileStamp>,
+) -> Vec<PathBuf> {
+    let mut changed: Vec<PathBuf> = current
+        .iter()
+        .filter(|(path, stamp)| previous.get(*path) != Some(stamp))
+        .map(|(path, _)| path.clone())
+        .collect();
+    changed.extend(
+        previous
+            .keys()
+            .filter(|path| !current.contains_key(*path))
+            .cloned(),
+    );
+
+    changed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn diff_finds_added_removed_and_modified() {
+        let t = SystemTime::UNIX_EPOCH;
+        let previous = BTreeMap::from([
+            (PathBuf::from("same"), (Some(t), 1)),
+            (PathBuf::from("modified"), (Some(t), 1)),
+            (PathBuf::from("removed"), (Some(t), 1)),
+        ]);
+        let current = BTreeMap::from([
+            (PathBuf::from("same"), (Some(t), 1)),
+            (PathBuf::from("modified"), (Some(t), 2)),
+            (PathBuf::from("added"), (Some(t), 1)),
+        ]);
+
+        assert_eq!(
+            diff(&previous, &current),
+            vec![
+                PathBuf::from("added"),
+                PathBuf::from("modified"),
+                PathBuf::from("removed"),
+            ]
+        );
+        assert!(diff(&current, &current).is_empty());
+    }
+}
@@ -48,7 +48,11 @@ async fn shuttle_run(working_directory: &str, external: bool) -> String {
                     release: false,
                     raw: false,
                     bacon: false,
+                    watch: false,
+                    all: false,
+                    cargo_args: Default::default(),
                     secret_args: Default::default(),
+                    log_filter: Default::default(),
                 }),
             },
             false,
@@ -8,6 +8,8 @@ pub mod secrets;
 pub mod tables;
 pub mod templates;
 
+use std::collections::BTreeMap;
+
 use serde::{Deserialize, Serialize};
 
 ////// Resource Input/Output types
@@ -18,6 +20,10 @@ pub struct DbInput {
     pub local_uri: Option<String>,
     /// Override the default db name. Only applies to RDS.
     pub db_name: Option<String>,
+    /// SQL file, or directory of SQL files, to run once on a local database that has not been seeded yet.
+    /// Relative to the project root. Only applies to local runs.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub seed: Option<String>,
 }
 
 /// The output produced by Shuttle DB resources
@@ -103,7 +109,7 @@ impl std::fmt::Debug for DatabaseInfo {
 }
 
 /// Used to request a container from the local run provisioner
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, Default)]
 pub struct ContainerRequest {
     pub project_name: String,
     /// Type of container, used in the container name. ex "qdrant"
@@ -112,8 +118,32 @@ pub struct ContainerRequest {
     pub image: String,
     /// The internal port that the container should expose. ex. "6334/tcp"
     pub port: String,
+    /// Other internal ports that the container should expose. ex. ["6333/tcp"]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub extra_ports: Vec<String>,
     /// list of "KEY=value" strings
     pub env: Vec<String>,
+    /// Command to run instead of the default command of the image
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub command: Option<Vec<String>>,
+    /// Volumes to mount, as "name:/path/in/container" strings
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub volumes: Vec<String>,
+    /// How to tell that the container is ready to be used after it starts
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub readiness: Option<ReadinessCheck>,
+}
+
+/// A check that passes once a container is ready to be used
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ReadinessCheck {
+    /// Run a command in the container, which exits successfully when it is ready
+    Exec { command: Vec<String> },
+    /// Connect to an exposed port. ex. "6334/tcp"
+    Tcp { port: String },
+    /// Send a GET request to a path on an exposed port, which returns a success status when it is ready
+    Http { port: String, path: String },
 }
 
 /// Response from requesting a container from the local run provisioner
@@ -122,6 +152,9 @@ pub struct ContainerResponse {
     /// The port that the container exposes to the host.
     /// Is a string for parity with the Docker respose.
     pub host_port: String,
+    /// The host ports of all exposed ports, keyed by the internal port. ex. {"6333/tcp": "49153"}
+    #[serde(default)]
+    pub host_ports: BTreeMap<String, String>,
 }
 
 /// Check if two versions are compatible based on the rule used by cargo:
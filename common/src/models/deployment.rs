@@ -8,6 +8,7 @@ use strum::{Display, EnumString};
 use crossterm::style::Stylize;
 
 use super::infra::InfraRequest;
+use crate::secrets::Secret;
 
 #[derive(Clone, Debug, Deserialize, Eq, PartialEq, Display, Serialize, EnumString)]
 #[serde(rename_all = "lowercase")]
@@ -94,6 +95,10 @@ pub struct DeploymentResponse {
     pub uris: Vec<String>,
     pub build_id: Option<String>,
     pub build_meta: Option<BuildMeta>,
+    pub build_args: Option<BuildArgs>,
+    pub infra: Option<InfraRequest>,
+    /// Keys of the secrets that were available to this deployment
+    pub secret_keys: Option<Vec<String>>,
 }
 
 #[cfg(feature = "display")]
@@ -108,10 +113,17 @@ impl DeploymentResponse {
     }
     pub fn to_string_colored(&self) -> String {
         // TODO: make this look nicer
+        let profile = self
+            .build_meta
+            .as_ref()
+            .and_then(|m| m.secrets_profile.as_deref())
+            .map(|p| format!("Secrets profile: {p}\n"))
+            .unwrap_or_default();
         format!(
-            "Deployment {} - {}\n{}",
+            "Deployment {} - {}\n{}{}",
             self.id.as_str().bold(),
             self.state.to_string_colored(),
+            profile,
             self.uris.join("\n"),
         )
     }
@@ -125,6 +137,41 @@ pub struct UploadArchiveResponse {
     pub archive_version_id: String,
 }
 
+/// Ask which of the given file blobs have not been uploaded to the project yet
+#[derive(Deserialize, Serialize)]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+#[typeshare::typeshare]
+pub struct ArchiveBlobsRequest {
+    /// Hex encoded SHA-256 hashes of file contents
+    pub hashes: Vec<String>,
+}
+
+#[derive(Deserialize, Serialize)]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+#[typeshare::typeshare]
+pub struct ArchiveBlobsResponse {
+    /// The hashes that need to be uploaded before an archive can be made from them
+    pub missing: Vec<String>,
+}
+
+/// Describes an archive in terms of previously uploaded file blobs
+#[derive(Deserialize, Serialize)]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+#[typeshare::typeshare]
+pub struct ArchiveManifest {
+    pub files: Vec<ArchiveManifestEntry>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+#[typeshare::typeshare]
+pub struct ArchiveManifestEntry {
+    /// Path of the file in the archive, using `/` as separator
+    pub path: String,
+    /// Hex encoded SHA-256 hash of the file contents
+    pub hash: String,
+}
+
 #[derive(Deserialize, Serialize)]
 #[serde(tag = "type", content = "content")]
 #[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
@@ -132,9 +179,10 @@ pub struct UploadArchiveResponse {
 pub enum DeploymentRequest {
     /// Build an image from the source code in an attached zip archive
     BuildArchive(Box<DeploymentRequestBuildArchive>),
-    // TODO?: Add GitRepo(DeploymentRequestGitRepo)
+    /// Build an image from the source code at a commit in a git repository
+    GitRepo(Box<DeploymentRequestGitRepo>),
     /// Use this image directly. Can be used to skip the build step.
-    Image(DeploymentRequestImage),
+    Image(Box<DeploymentRequestImage>),
     //
     // No Unknown variant: is a Request type and should only be deserialized on backend
 }
@@ -153,6 +201,23 @@ pub struct DeploymentRequestBuildArchive {
     pub infra: Option<InfraRequest>,
 }
 
+#[derive(Default, Deserialize, Serialize)]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+#[typeshare::typeshare]
+pub struct DeploymentRequestGitRepo {
+    /// URL of the repository to clone
+    pub url: String,
+    /// The full hash of the commit to build
+    pub commit: String,
+    /// Path to the project directory within the repository
+    pub subfolder: Option<String>,
+    pub build_args: Option<BuildArgs>,
+    /// TODO: Remove this in favour of a separate secrets uploading action.
+    pub secrets: Option<HashMap<String, String>>,
+    pub build_meta: Option<BuildMeta>,
+    pub infra: Option<InfraRequest>,
+}
+
 #[derive(Deserialize, Serialize)]
 #[serde(tag = "type", content = "content")]
 #[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
@@ -211,6 +276,8 @@ pub struct BuildMeta {
     pub git_commit_msg: Option<String>,
     pub git_branch: Option<String>,
     pub git_dirty: Option<bool>,
+    /// Name of the secrets profile used for this deployment
+    pub secrets_profile: Option<String>,
 }
 
 #[cfg(feature = "display")]
@@ -237,7 +304,18 @@ pub struct DeploymentRequestImage {
     pub image: String,
     /// TODO: Remove this in favour of a separate secrets uploading action.
     pub secrets: Option<HashMap<String, String>>,
-    // TODO: credentials fields for private repos??
+    /// Credentials for pulling the image from a private registry
+    pub credentials: Option<RegistryCredentials>,
+    pub build_meta: Option<BuildMeta>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+#[typeshare::typeshare]
+pub struct RegistryCredentials {
+    pub username: String,
+    #[typeshare(serialized_as = "String")]
+    pub password: Secret<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -313,4 +391,17 @@ mod tests {
         assert_eq!(format!("{}", Environment::Local), "local".to_owned());
         assert_eq!(Environment::Local.to_string(), "local".to_owned());
     }
+
+    #[test]
+    fn registry_credentials_hide_password() {
+        let credentials = RegistryCredentials {
+            username: "user".to_owned(),
+            password: Secret::new("VERYSECRET".to_owned()),
+        };
+
+        assert!(!format!("{credentials:?}").contains("VERYSECRET"));
+        assert!(serde_json::to_string(&credentials)
+            .unwrap()
+            .contains(r#""password":"VERYSECRET""#));
+    }
 }
@@ -1,3 +1,5 @@
+use std::collections::HashMap;
+
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
@@ -64,6 +66,18 @@ pub struct ResourceListResponse {
     pub resources: Vec<ResourceResponse>,
 }
 
+/// Partial update of the secrets in a project.
+/// Keys in `set` are added or overwritten, keys in `unset` are removed.
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+#[typeshare::typeshare]
+pub struct SecretsUpdateRequest {
+    #[serde(default)]
+    pub set: HashMap<String, String>,
+    #[serde(default)]
+    pub unset: Vec<String>,
+}
+
 #[derive(
     Clone,
     Debug,
@@ -71,6 +71,10 @@ impl SecretStore {
     pub fn get(&self, key: &str) -> Option<String> {
         self.secrets.get(key).map(|s| s.expose().to_owned())
     }
+
+    pub fn is_empty(&self) -> bool {
+        self.secrets.is_empty()
+    }
 }
 
 impl IntoIterator for SecretStore {
@@ -181,3 +181,24 @@ fn get_secrets_table(secrets: &[ResourceResponse], service_name: &str, raw: bool
 
     format!("These secrets can be accessed by {service_name}\n{table}")
 }
+
+pub fn get_secret_values_table(secrets: &SecretStore, raw: bool, show_values: bool) -> String {
+    let mut table = Table::new();
+    table
+        .load_preset(if raw { NOTHING } else { UTF8_BORDERS_ONLY })
+        .set_content_arrangement(ContentArrangement::Disabled)
+        .set_header(vec!["Key", "Value"]);
+
+    for (key, value) in secrets.secrets.iter() {
+        table.add_row(vec![
+            Cell::new(key).add_attribute(Attribute::Bold),
+            Cell::new(if show_values {
+                value.expose()
+            } else {
+                value.redacted()
+            }),
+        ]);
+    }
+
+    table.to_string()
+}
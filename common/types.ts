@@ -31,6 +31,29 @@ export interface ApiError {
 	status_code: number;
 }
 
+/** Ask which of the given file blobs have not been uploaded to the project yet */
+export interface ArchiveBlobsRequest {
+	/** Hex encoded SHA-256 hashes of file contents */
+	hashes: string[];
+}
+
+export interface ArchiveBlobsResponse {
+	/** The hashes that need to be uploaded before an archive can be made from them */
+	missing: string[];
+}
+
+export interface ArchiveManifestEntry {
+	/** Path of the file in the archive, using `/` as separator */
+	path: string;
+	/** Hex encoded SHA-256 hash of the file contents */
+	hash: string;
+}
+
+/** Describes an archive in terms of previously uploaded file blobs */
+export interface ArchiveManifest {
+	files: ArchiveManifestEntry[];
+}
+
 export interface BetterstackConfig {
 	ingesting_host: string;
 	source_token: string;
@@ -60,6 +83,8 @@ export interface BuildMeta {
 	git_commit_msg?: string;
 	git_branch?: string;
 	git_dirty?: boolean;
+	/** Name of the secrets profile used for this deployment */
+	secrets_profile?: string;
 }
 
 export interface CertificateResponse {
@@ -136,6 +161,10 @@ export interface DeploymentResponse {
 	uris: string[];
 	build_id?: string;
 	build_meta?: BuildMeta;
+	build_args?: BuildArgs;
+	infra?: InfraRequest;
+	/** Keys of the secrets that were available to this deployment */
+	secret_keys?: string[];
 }
 
 export interface DeploymentListResponse {
@@ -172,10 +201,32 @@ export interface DeploymentRequestBuildArchive {
 	infra?: InfraRequest;
 }
 
+export interface DeploymentRequestGitRepo {
+	/** URL of the repository to clone */
+	url: string;
+	/** The full hash of the commit to build */
+	commit: string;
+	/** Path to the project directory within the repository */
+	subfolder?: string;
+	build_args?: BuildArgs;
+	/** TODO: Remove this in favour of a separate secrets uploading action. */
+	secrets?: Record<string, string>;
+	build_meta?: BuildMeta;
+	infra?: InfraRequest;
+}
+
+export interface RegistryCredentials {
+	username: string;
+	password: string;
+}
+
 export interface DeploymentRequestImage {
 	image: string;
 	/** TODO: Remove this in favour of a separate secrets uploading action. */
 	secrets?: Record<string, string>;
+	/** Credentials for pulling the image from a private registry */
+	credentials?: RegistryCredentials;
+	build_meta?: BuildMeta;
 }
 
 export interface GrafanaCloudConfig {
@@ -322,6 +373,15 @@ export interface ResourceListResponse {
 	resources: ResourceResponse[];
 }
 
+/**
+ * Partial update of the secrets in a project.
+ * Keys in `set` are added or overwritten, keys in `unset` are removed.
+ */
+export interface SecretsUpdateRequest {
+	set: Record<string, string>;
+	unset: string[];
+}
+
 export enum SubscriptionType {
 	Pro = "pro",
 	Rds = "rds",
@@ -463,6 +523,8 @@ export interface UserUsageResponse {
 export type DeploymentRequest = 
 	/** Build an image from the source code in an attached zip archive */
 	| { type: "BuildArchive", content: DeploymentRequestBuildArchive }
+	/** Build an image from the source code at a commit in a git repository */
+	| { type: "GitRepo", content: DeploymentRequestGitRepo }
 	/** Use this image directly. Can be used to skip the build step. */
 	| { type: "Image", content: DeploymentRequestImage };
 
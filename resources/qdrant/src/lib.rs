@@ -3,8 +3,8 @@ use serde::{Deserialize, Serialize};
 use shuttle_service::{
     error::{CustomError, Error},
     resource::{ProvisionResourceRequest, ResourceType},
-    ContainerRequest, ContainerResponse, Environment, IntoResource, ResourceFactory,
-    ResourceInputBuilder,
+    ContainerRequest, ContainerResponse, Environment, IntoResource, ReadinessCheck,
+    ResourceFactory, ResourceInputBuilder,
 };
 
 /// A Qdrant vector database
@@ -71,7 +71,12 @@ impl ResourceInputBuilder for Qdrant {
                         container_name: "qdrant".to_string(),
                         image: "docker.io/qdrant/qdrant:v1.10.0".to_string(),
                         port: "6334/tcp".to_string(),
-                        env: vec![],
+                        extra_ports: vec!["6333/tcp".to_string()],
+                        readiness: Some(ReadinessCheck::Http {
+                            port: "6333/tcp".to_string(),
+                            path: "/readyz".to_string(),
+                        }),
+                        ..Default::default()
                     })
                     .unwrap(),
                 })),
@@ -0,0 +1,140 @@
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use shuttle_service::{
+    error::{CustomError, Error},
+    resource::{ProvisionResourceRequest, ResourceType},
+    ContainerRequest, ContainerResponse, Environment, IntoResource, ReadinessCheck,
+    ResourceFactory, ResourceInputBuilder,
+};
+
+/// A Redis (or Valkey) server
+#[derive(Default, Serialize)]
+pub struct Redis {
+    /// Required if deploying. Use a secret, such as `{secrets.REDIS_URL}`, to keep the password out of the code.
+    cloud_url: Option<String>,
+    /// If given, use this instead of the default docker container on local run
+    local_url: Option<String>,
+}
+
+impl Redis {
+    pub fn cloud_url(mut self, cloud_url: &str) -> Self {
+        self.cloud_url = Some(cloud_url.to_string());
+        self
+    }
+    pub fn local_url(mut self, local_url: &str) -> Self {
+        self.local_url = Some(local_url.to_string());
+        self
+    }
+}
+
+/// Conditionally request a Shuttle resource
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum MaybeRequest {
+    Request(ProvisionResourceRequest),
+    NotRequest(String),
+}
+
+#[async_trait]
+impl ResourceInputBuilder for Redis {
+    type Input = MaybeRequest;
+    // The response can be a provisioned container, depending on local/deployment and config.
+    type Output = OutputWrapper;
+
+    async fn build(self, factory: &ResourceFactory) -> Result<Self::Input, Error> {
+        let md = factory.get_metadata();
+        match md.env {
+            Environment::Deployment => match self.cloud_url {
+                Some(cloud_url) => Ok(MaybeRequest::NotRequest(cloud_url)),
+                None => Err(Error::Custom(CustomError::msg(
+                    "missing `cloud_url` parameter",
+                ))),
+            },
+            Environment::Local => match self.local_url {
+                Some(local_url) => Ok(MaybeRequest::NotRequest(local_url)),
+                None => Ok(MaybeRequest::Request(ProvisionResourceRequest {
+                    r#type: ResourceType::Container,
+                    config: serde_json::to_value(ContainerRequest {
+                        project_name: md.project_name,
+                        container_name: "redis".to_string(),
+                        image: "docker.io/library/redis:7".to_string(),
+                        port: "6379/tcp".to_string(),
+                        readiness: Some(ReadinessCheck::Exec {
+                            command: vec!["redis-cli".to_string(), "ping".to_string()],
+                        }),
+                        ..Default::default()
+                    })
+                    .unwrap(),
+                })),
+            },
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum OutputWrapper {
+    Container(ContainerResponse),
+    Url(String),
+}
+
+impl OutputWrapper {
+    fn url(self) -> String {
+        match self {
+            Self::Container(output) => format!("redis://localhost:{}", output.host_port),
+            Self::Url(url) => url,
+        }
+    }
+}
+
+#[async_trait]
+impl IntoResource<String> for OutputWrapper {
+    async fn into_resource(self) -> Result<String, Error> {
+        Ok(self.url())
+    }
+}
+
+#[async_trait]
+impl IntoResource<redis::Client> for OutputWrapper {
+    async fn into_resource(self) -> Result<redis::Client, Error> {
+        redis::Client::open(self.url()).map_err(|err| Error::Custom(err.into()))
+    }
+}
+
+#[async_trait]
+impl IntoResource<redis::aio::ConnectionManager> for OutputWrapper {
+    async fn into_resource(self) -> Result<redis::aio::ConnectionManager, Error> {
+        let client: redis::Client = self.into_resource().await?;
+
+        client
+            .get_connection_manager()
+            .await
+            .map_err(|err| Error::Custom(err.into()))
+    }
+}
+
+#[cfg(feature = "bb8")]
+#[async_trait]
+impl IntoResource<bb8_redis::bb8::Pool<bb8_redis::RedisConnectionManager>> for OutputWrapper {
+    async fn into_resource(
+        self,
+    ) -> Result<bb8_redis::bb8::Pool<bb8_redis::RedisConnectionManager>, Error> {
+        let manager = bb8_redis::RedisConnectionManager::new(self.url())
+            .map_err(|err| Error::Custom(err.into()))?;
+
+        bb8_redis::bb8::Pool::builder()
+            .build(manager)
+            .await
+            .map_err(|err| Error::Custom(err.into()))
+    }
+}
+
+#[cfg(feature = "deadpool")]
+#[async_trait]
+impl IntoResource<deadpool_redis::Pool> for OutputWrapper {
+    async fn into_resource(self) -> Result<deadpool_redis::Pool, Error> {
+        deadpool_redis::Config::from_url(self.url())
+            .create_pool(Some(deadpool_redis::Runtime::Tokio1))
+            .map_err(|err| Error::Custom(err.into()))
+    }
+}
@@ -31,6 +31,13 @@ impl Postgres {
 
         self
     }
+
+    /// Run an SQL file, or the SQL files in a directory, once on the local database, unless it was seeded before
+    pub fn seed(mut self, path: &str) -> Self {
+        self.0.seed = Some(path.to_string());
+
+        self
+    }
 }
 
 /// Conditionally request a Shuttle resource
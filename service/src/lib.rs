@@ -10,7 +10,7 @@ pub use shuttle_common::{
         resource,
     },
     secrets::{Secret, SecretStore},
-    ContainerRequest, ContainerResponse, DatabaseInfo, DatabaseResource, DbInput,
+    ContainerRequest, ContainerResponse, DatabaseInfo, DatabaseResource, DbInput, ReadinessCheck,
 };
 
 pub use crate::error::{CustomError, Error};